@@ -0,0 +1,162 @@
+//! Mix two palettes into intermediate ones, e.g. dusk themes between a
+//! light and a dark palette.
+
+use crate::{Palette, Result, color::Oklab, edit::PaletteDocument, palette::ColorRef};
+
+/// One intermediate palette.
+#[derive(Debug, Clone)]
+pub struct Blend {
+    /// How far along from the first palette (0) to the second (1).
+    pub t: f32,
+    /// Palette TOML.
+    pub toml: String,
+}
+
+/// `steps` evenly spaced mixes of palette `a` into palette `b`, not counting
+/// either end. Every literal color of `a` is interpolated in OKLab toward the
+/// color the same key resolves to in `b`; keys, references, comments and
+/// layout come from `a`, and keys missing from `b` keep `a`'s color.
+///
+/// A reference in `a` is kept when `b` holds the same reference, since it
+/// follows its blended target. Otherwise, when it resolves to a different
+/// color in `b`, it is replaced by a blended literal.
+pub fn blend(a: &str, b: &str, steps: usize) -> Result<Vec<Blend>> {
+    let palette_a = Palette::parse(a)?;
+    let palette_b = Palette::parse(b)?;
+    let resolved_a = palette_a.resolve()?;
+    let resolved_b = palette_b.resolve()?;
+    let doc_a: PaletteDocument = a.parse()?;
+    let doc_b: PaletteDocument = b.parse()?;
+
+    // Colors written in `a`, paired with their counterpart in `b`.
+    let generated = palette_a.generated_keys();
+    let mut pairs: Vec<(String, String, String)> = palette_a
+        .entries()
+        .into_iter()
+        .filter(|(key, _)| !generated.contains(key))
+        .filter_map(|(key, cref)| {
+            let from = resolved_a.trace[&key].hex.clone();
+            let to = resolved_b.trace.get(&key).map(|t| t.hex.clone());
+            match cref {
+                ColorRef::Hex(_) => Some((key, from.clone(), to.unwrap_or(from))),
+                ColorRef::Path(path) => {
+                    let to = to.filter(|to| *to != from)?;
+                    (doc_b.get(&key) != Some(path.as_str())).then_some((key, from, to))
+                }
+            }
+        })
+        .collect();
+
+    // Scale bases written as hex blend with the other palette's base. A
+    // deriving tone's shared copy isn't in the file and follows its source.
+    for (label, scale) in palette_a.scales() {
+        if !matches!(scale.scale, ColorRef::Hex(_)) {
+            continue;
+        }
+        let key = format!("{label}.scale");
+        let Some(from) = doc_a.get(&key) else {
+            continue;
+        };
+        let to = doc_b
+            .get(&key)
+            .filter(|v| v.starts_with('#'))
+            .unwrap_or(from);
+        pairs.push((key.clone(), from.to_uppercase(), to.to_uppercase()));
+    }
+
+    let name_b = &palette_b.meta.name;
+    (1..=steps)
+        .map(|i| {
+            let t = i as f32 / (steps + 1) as f32;
+            let mut doc = doc_a.clone();
+            for (key, from, to) in &pairs {
+                doc.set_str(key, &mix(from, to, t))?;
+            }
+            let name = format!("{} → {name_b} ({:.0}%)", palette_a.meta.name, t * 100.0);
+            doc.set_str("meta.name", &name)?;
+            Ok(Blend {
+                t,
+                toml: doc.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn mix(from: &str, to: &str, t: f32) -> String {
+    match (Oklab::from_hex(from), Oklab::from_hex(to)) {
+        (Some(a), Some(b)) => a.lerp(b, t).to_hex(),
+        _ => from.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::TEST_PALETTE;
+
+    #[test]
+    fn interpolates_literals_and_keeps_references() {
+        let day = TEST_PALETTE.replace("name = \"Demo\"", "name = \"Day\"");
+        let night = day
+            .replace("name = \"Day\"", "name = \"Night\"")
+            .replace("white = \"colors.light.text\"", "white = \"#333333\"")
+            .replace("background = \"#FFFFFF\"", "background = \"#000000\"")
+            .replace("scale = \"#3366CC\"", "scale = \"#CC6633\"");
+        let blends = blend(&day, &night, 3).unwrap();
+        assert_eq!(blends.len(), 3);
+        assert_eq!(blends[1].t, 0.5);
+        assert!(blends[1].toml.contains("# paper"));
+
+        let mid = Palette::parse(&blends[1].toml).unwrap();
+        assert_eq!(mid.meta.name, "Day → Night (50%)");
+        let resolved = mid.resolve().unwrap();
+        let background = mix("#FFFFFF", "#000000", 0.5);
+        assert_eq!(resolved.trace["colors.light.background"].hex, background);
+        assert_eq!(resolved.trace["ansi.light.normal.black"].hex, background);
+        assert_eq!(
+            resolved.trace["ansi.light.normal.black"].chain,
+            ["colors.light.background"]
+        );
+        assert_eq!(resolved.trace["colors.light.text"].hex, "#111111");
+        // A reference meeting a different literal in `b` becomes a literal.
+        let white = &resolved.trace["ansi.light.normal.white"];
+        assert!(white.chain.is_empty(), "{:?}", white.chain);
+        assert_eq!(white.hex, mix("#111111", "#333333", 0.5));
+
+        let scale = &resolved.trace["colors.light.blue.900"].hex;
+        assert_ne!(
+            scale,
+            &Palette::parse(&day).unwrap().resolve().unwrap().trace["colors.light.blue.900"].hex
+        );
+    }
+
+    #[test]
+    fn blends_scales_next_to_a_derived_tone() {
+        let start = TEST_PALETTE.find("[colors.dark]").unwrap();
+        let end = TEST_PALETTE.find("[accents]").unwrap();
+        let day = format!(
+            "{}[colors.dark]\nderive = \"light\"\n\n{}",
+            &TEST_PALETTE[..start],
+            &TEST_PALETTE[end..]
+        );
+        let night = day.replace("scale = \"#3366CC\"", "scale = \"#CC6633\"");
+        let blends = blend(&day, &night, 1).unwrap();
+
+        // Only the written scale blends; the dark tone still derives.
+        assert_eq!(blends[0].toml.matches("scale = ").count(), 1);
+        assert!(blends[0].toml.contains("derive = \"light\""));
+        let mid = Palette::parse(&blends[0].toml).unwrap();
+        assert!(
+            mid.colors
+                .derived
+                .contains(&"colors.dark.primary".to_string())
+        );
+        let resolved = mid.resolve().unwrap();
+        let light = &resolved.trace["colors.light.blue.900"].hex;
+        assert_eq!(&resolved.trace["colors.dark.blue.900"].hex, light);
+        assert_ne!(
+            light,
+            &Palette::parse(&day).unwrap().resolve().unwrap().trace["colors.light.blue.900"].hex
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
+use veneer_theme::{export::Format, graph::GraphFormat, lint, palette::Tone};
+
+use crate::preview::Scene;
 
 /// Veneer CLI entrypoint.
 #[derive(Parser, Debug)]
@@ -21,6 +24,9 @@ pub enum Command {
         /// Palette TOML file.
         #[arg(long, default_value = "veneer.toml")]
         palette: PathBuf,
+        /// Check every template for undefined variables and bad helper arguments before rendering.
+        #[arg(long)]
+        strict: bool,
     },
     /// Validate palette + template without writing outputs.
     Check {
@@ -29,11 +35,223 @@ pub enum Command {
         palette: PathBuf,
         /// Template file to render (must end with .tera).
         template: PathBuf,
+        /// Also report undefined variables and bad helper arguments, with line numbers.
+        #[arg(long)]
+        strict: bool,
     },
     /// Show palette values with color swatches.
     Show {
         /// Palette TOML file.
         #[arg(long, default_value = "veneer.toml")]
         palette: PathBuf,
+        /// Show how each reference resolves, e.g. `colors.dark.background → #0E1117`.
+        #[arg(long)]
+        trace: bool,
+        /// Render sample content instead of swatches: terminal, code, diff or ls (comma-separated).
+        #[arg(long, value_delimiter = ',')]
+        preview: Vec<Scene>,
+        /// Paint a preview role with a palette key, e.g. `keyword=tokens.syntax.keyword`.
+        #[arg(long = "map", value_name = "ROLE=KEY", value_parser = parse_mapping)]
+        mappings: Vec<(String, String)>,
+    },
+    /// Edit colors interactively in the terminal, with live contrast and previews.
+    Edit {
+        /// Palette TOML file.
+        #[arg(long, default_value = "veneer.toml")]
+        palette: PathBuf,
+    },
+    /// Explain how a key resolves and list the keys that depend on it.
+    Why {
+        /// Palette TOML file.
+        #[arg(long, default_value = "veneer.toml")]
+        palette: PathBuf,
+        /// Dotted palette path, e.g. `ansi.dark.normal.black`.
+        path: String,
+    },
+    /// List every key and template affected by changing a key.
+    Deps {
+        /// Palette TOML file.
+        #[arg(long, default_value = "veneer.toml")]
+        palette: PathBuf,
+        /// Dotted palette path, e.g. `colors.dark.background`.
+        path: String,
+        /// Also scan templates: a file, a directory of `*.tera`, or a glob.
+        #[arg(long)]
+        templates: Option<PathBuf>,
+    },
+    /// Compare two palettes, or a palette against an earlier git revision of itself.
+    Diff {
+        /// Old palette file (omit with --rev).
+        #[arg(required_unless_present = "rev", conflicts_with = "rev")]
+        old: Option<PathBuf>,
+        /// New palette file (omit with --rev).
+        #[arg(required_unless_present = "rev", conflicts_with = "rev")]
+        new: Option<PathBuf>,
+        /// Compare --palette in the working tree against this git revision.
+        #[arg(long)]
+        rev: Option<String>,
+        /// Palette TOML file, with --rev.
+        #[arg(long, default_value = "veneer.toml")]
+        palette: PathBuf,
+        /// Print JSON instead of swatches.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Draw the reference graph as a Graphviz or Mermaid diagram.
+    Graph {
+        /// Palette TOML file.
+        #[arg(long, default_value = "veneer.toml")]
+        palette: PathBuf,
+        /// Diagram format: dot or mermaid.
+        #[arg(long, default_value = "dot")]
+        format: GraphFormat,
+        /// Write to a file instead of stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Report duplicate colors, ANSI problems, unused keys and undefined template variables.
+    Lint {
+        /// Palette TOML file.
+        #[arg(long, default_value = "veneer.toml")]
+        palette: PathBuf,
+        /// Templates to check against: a file, a directory of `*.tera`, or a glob.
+        #[arg(long)]
+        templates: Option<PathBuf>,
+        /// CIEDE2000 distance under which distinct colors count as near-duplicates.
+        #[arg(long, default_value_t = lint::NEAR_DUPLICATE_THRESHOLD)]
+        threshold: f32,
+        /// CIEDE2000 distance every pair of ANSI colors in a row must exceed.
+        #[arg(long, default_value_t = lint::ANSI_DISTINCT_THRESHOLD)]
+        ansi_threshold: f32,
+        /// Rewrite exact duplicate colors as references to the first key of the same tone using them.
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Rewrite the palette file in canonical form, keeping comments.
+    Fmt {
+        /// Palette TOML file.
+        #[arg(long, default_value = "veneer.toml")]
+        palette: PathBuf,
+        /// Don't write; exit non-zero if the file isn't formatted.
+        #[arg(long)]
+        check: bool,
+    },
+    /// Print the resolved hex value of one color key.
+    Get {
+        /// Dotted key, e.g. `colors.dark.background`.
+        path: String,
+        /// Palette TOML file.
+        #[arg(long, default_value = "veneer.toml")]
+        palette: PathBuf,
+    },
+    /// Set one color key to a hex color or a reference, keeping comments and layout.
+    Set {
+        /// Dotted key, e.g. `colors.dark.background`; added if missing.
+        path: String,
+        /// Hex color (`#RRGGBB`) or dotted path to another key.
+        value: String,
+        /// Palette TOML file.
+        #[arg(long, default_value = "veneer.toml")]
+        palette: PathBuf,
     },
+    /// Print the fully resolved palette to stdout.
+    Resolve {
+        /// Palette TOML file.
+        #[arg(long, default_value = "veneer.toml")]
+        palette: PathBuf,
+        /// Output format: json, toml, yaml, or css (custom properties).
+        #[arg(long, default_value = "json")]
+        format: Format,
+        /// Only include one variant (light or dark).
+        #[arg(long)]
+        tone: Option<Tone>,
+        /// Flatten to `path = value` pairs.
+        #[arg(long)]
+        flat: bool,
+    },
+    /// Export the palette as W3C Design Tokens (DTCG) JSON, keeping references as aliases.
+    ///
+    /// Tonal scales are written as their step colors and a `derive` tone as
+    /// the colors it derives, so importing the result gives the same colors
+    /// but not the same palette source.
+    Export {
+        /// Palette TOML file.
+        #[arg(long, default_value = "veneer.toml")]
+        palette: PathBuf,
+        /// Write to this file instead of stdout.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Convert a W3C Design Tokens (DTCG) JSON file into palette TOML.
+    ///
+    /// Only color tokens are supported. Palettes exported by `veneer export`
+    /// come back with scales and `derive` written out as literal colors.
+    Import {
+        /// DTCG JSON file.
+        tokens: PathBuf,
+        /// Write to this file instead of stdout.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Generate a palette from a seed color, with contrast-checked tones and ANSI rows.
+    New {
+        /// Seed color; becomes the primary color and tints the neutrals.
+        #[arg(long)]
+        seed: String,
+        /// Keep this background for the tone it belongs to (light or dark).
+        #[arg(long)]
+        background: Option<String>,
+        /// Keep this text color for the same tone as the background.
+        #[arg(long)]
+        foreground: Option<String>,
+        /// Palette name written to `meta.name`.
+        #[arg(long, default_value = "Untitled")]
+        name: String,
+        /// Write to this file instead of stdout.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Write colors computed by `derive = "light"` (or `"dark"`) into the palette file.
+    Derive {
+        /// Palette TOML file.
+        #[arg(long, default_value = "veneer.toml")]
+        palette: PathBuf,
+    },
+    /// Write palettes mixed between two palettes, interpolating every color in OKLab.
+    Blend {
+        /// Palette to start from; keys, references and layout come from it.
+        from: PathBuf,
+        /// Palette to blend toward.
+        to: PathBuf,
+        /// Number of intermediate palettes.
+        #[arg(long, default_value_t = 5)]
+        steps: usize,
+        /// Directory to write `<from>-<to>-<percent>.toml` files into.
+        #[arg(long, default_value = ".")]
+        out_dir: PathBuf,
+    },
+    /// Propose a palette from the dominant colors of a PNG or JPEG image.
+    Extract {
+        /// Image to take colors from.
+        image: PathBuf,
+        /// Number of colors to cluster the image into.
+        #[arg(long, default_value_t = 8)]
+        colors: usize,
+        /// Palette name written to `meta.name` (default: the image's file name).
+        #[arg(long)]
+        name: Option<String>,
+        /// Palette file to write.
+        #[arg(long, short, default_value = "veneer.toml")]
+        output: PathBuf,
+    },
+}
+
+/// Split a `ROLE=KEY` preview mapping.
+fn parse_mapping(raw: &str) -> Result<(String, String), String> {
+    match raw.split_once('=') {
+        Some((role, key)) if !role.is_empty() && !key.is_empty() => {
+            Ok((role.to_string(), key.to_string()))
+        }
+        _ => Err(format!("expected ROLE=KEY, got '{raw}'")),
+    }
 }
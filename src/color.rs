@@ -0,0 +1,386 @@
+/// Parse `#RRGGBB` into its channels.
+pub fn hex_to_rgb(hex: &str) -> Option<(u8, u8, u8)> {
+    if hex.len() != 7 || !hex.starts_with('#') || !hex[1..].bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let r = u8::from_str_radix(&hex[1..3], 16).ok()?;
+    let g = u8::from_str_radix(&hex[3..5], 16).ok()?;
+    let b = u8::from_str_radix(&hex[5..7], 16).ok()?;
+    Some((r, g, b))
+}
+
+pub fn rgb_to_hex(r: u8, g: u8, b: u8) -> String {
+    format!("#{r:02X}{g:02X}{b:02X}")
+}
+
+/// WCAG relative luminance of `#RRGGBB`, from 0 (black) to 1 (white).
+pub fn relative_luminance(hex: &str) -> Option<f32> {
+    let (r, g, b) = hex_to_rgb(hex)?;
+    Some(0.2126 * srgb_to_linear(r) + 0.7152 * srgb_to_linear(g) + 0.0722 * srgb_to_linear(b))
+}
+
+/// WCAG contrast ratio between two colors, from 1 to 21.
+pub fn contrast_ratio(a: &str, b: &str) -> Option<f32> {
+    let (la, lb) = (relative_luminance(a)?, relative_luminance(b)?);
+    let (hi, lo) = if la > lb { (la, lb) } else { (lb, la) };
+    Some((hi + 0.05) / (lo + 0.05))
+}
+
+/// Color in the OKLab perceptual space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+impl Oklab {
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let (r, g, b) = hex_to_rgb(hex)?;
+        Some(Self::from_rgb(r, g, b))
+    }
+
+    pub fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+        let r = srgb_to_linear(r);
+        let g = srgb_to_linear(g);
+        let b = srgb_to_linear(b);
+
+        let l = 0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b;
+        let m = 0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b;
+        let s = 0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b;
+
+        let l = l.cbrt();
+        let m = m.cbrt();
+        let s = s.cbrt();
+
+        Self {
+            l: 0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            a: 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            b: 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        }
+    }
+
+    /// Convert back to sRGB, clamping out-of-gamut channels.
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        let (r, g, b) = self.to_linear_rgb();
+        (linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b))
+    }
+
+    /// Whether the color is representable in sRGB without clamping.
+    pub fn in_gamut(self) -> bool {
+        let (r, g, b) = self.to_linear_rgb();
+        [r, g, b].iter().all(|c| (-1e-4..=1.0 + 1e-4).contains(c))
+    }
+
+    fn to_linear_rgb(self) -> (f32, f32, f32) {
+        let l = self.l + 0.396_337_78 * self.a + 0.215_803_76 * self.b;
+        let m = self.l - 0.105_561_346 * self.a - 0.063_854_17 * self.b;
+        let s = self.l - 0.089_484_18 * self.a - 1.291_485_5 * self.b;
+
+        let l = l * l * l;
+        let m = m * m * m;
+        let s = s * s * s;
+
+        let r = 4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s;
+        let g = -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s;
+        let b = -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s;
+        (r, g, b)
+    }
+
+    pub fn to_hex(self) -> String {
+        let (r, g, b) = self.to_rgb();
+        rgb_to_hex(r, g, b)
+    }
+
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        Self {
+            l: self.l + (other.l - self.l) * t,
+            a: self.a + (other.a - self.a) * t,
+            b: self.b + (other.b - self.b) * t,
+        }
+    }
+
+    /// Euclidean distance in OKLab.
+    pub fn distance(self, other: Self) -> f32 {
+        let dl = self.l - other.l;
+        let da = self.a - other.a;
+        let db = self.b - other.b;
+        (dl * dl + da * da + db * db).sqrt()
+    }
+}
+
+/// OKLab in polar form: lightness, chroma and hue in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklch {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32,
+}
+
+impl Oklch {
+    pub fn new(l: f32, c: f32, h: f32) -> Self {
+        Self {
+            l,
+            c,
+            h: h.rem_euclid(360.0),
+        }
+    }
+
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        Some(Self::from(Oklab::from_hex(hex)?))
+    }
+
+    /// Hex for this color, reducing chroma until it fits in sRGB so the hue
+    /// and lightness survive.
+    pub fn to_hex(self) -> String {
+        let mut chroma = self.c.max(0.0);
+        loop {
+            let lab = Oklab::from(Self { c: chroma, ..self });
+            if chroma <= 0.0 || lab.in_gamut() {
+                return lab.to_hex();
+            }
+            chroma = (chroma - 0.002).max(0.0);
+        }
+    }
+}
+
+impl From<Oklab> for Oklch {
+    fn from(lab: Oklab) -> Self {
+        Self::new(lab.l, lab.a.hypot(lab.b), lab.b.atan2(lab.a).to_degrees())
+    }
+}
+
+impl From<Oklch> for Oklab {
+    fn from(lch: Oklch) -> Self {
+        let h = lch.h.to_radians();
+        Self {
+            l: lch.l,
+            a: lch.c * h.cos(),
+            b: lch.c * h.sin(),
+        }
+    }
+}
+
+/// Mirror a color's OKLCH lightness (`l` -> `1 - l`), keeping hue and chroma
+/// as far as the sRGB gamut allows.
+pub fn invert_lightness(hex: &str) -> Option<String> {
+    let color = Oklch::from_hex(hex)?;
+    Some(Oklch::new(1.0 - color.l, color.c, color.h).to_hex())
+}
+
+/// OKLCH lightness of scale steps 0 and 1000; steps in between are spaced
+/// evenly.
+const SCALE_LIGHTEST: f32 = 0.985;
+const SCALE_DARKEST: f32 = 0.235;
+
+/// Tonal scale steps (0–1000, e.g. Tailwind's 50–950) of a base color: hue
+/// and chroma come from the base, lightness falls evenly with the step.
+pub fn tonal_scale(hex: &str, steps: &[u16]) -> Option<Vec<String>> {
+    let base = Oklch::from_hex(hex)?;
+    Some(
+        steps
+            .iter()
+            .map(|&step| {
+                let t = f32::from(step) / 1000.0;
+                let l = SCALE_LIGHTEST + (SCALE_DARKEST - SCALE_LIGHTEST) * t;
+                Oklch::new(l, base.c, base.h).to_hex()
+            })
+            .collect(),
+    )
+}
+
+/// Color in CIE L*a*b* (D65), the space CIEDE2000 is defined on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+impl Lab {
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let (r, g, b) = hex_to_rgb(hex)?;
+        Some(Self::from_rgb(r, g, b))
+    }
+
+    pub fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+        let r = srgb_to_linear(r);
+        let g = srgb_to_linear(g);
+        let b = srgb_to_linear(b);
+
+        // XYZ relative to the D65 white point.
+        let x = (0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b) / 0.950_47;
+        let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b;
+        let z = (0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b) / 1.088_83;
+
+        let f = |t: f32| {
+            if t > 216.0 / 24_389.0 {
+                t.cbrt()
+            } else {
+                (24_389.0 / 27.0 * t + 16.0) / 116.0
+            }
+        };
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+        Self {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+
+    /// CIEDE2000 color difference; about 1.0 is the smallest difference most
+    /// people notice side by side.
+    pub fn delta_e(self, other: Self) -> f32 {
+        use std::f32::consts::PI;
+
+        let c1 = self.a.hypot(self.b);
+        let c2 = other.a.hypot(other.b);
+        let c_bar7 = ((c1 + c2) / 2.0).powi(7);
+        let g = 0.5 * (1.0 - (c_bar7 / (c_bar7 + 25f32.powi(7))).sqrt());
+
+        let a1 = self.a * (1.0 + g);
+        let a2 = other.a * (1.0 + g);
+        let c1 = a1.hypot(self.b);
+        let c2 = a2.hypot(other.b);
+        let hue = |b: f32, a: f32| {
+            if a == 0.0 && b == 0.0 {
+                0.0
+            } else {
+                b.atan2(a).to_degrees().rem_euclid(360.0)
+            }
+        };
+        let h1 = hue(self.b, a1);
+        let h2 = hue(other.b, a2);
+
+        let dl = other.l - self.l;
+        let dc = c2 - c1;
+        let dh = if c1 * c2 == 0.0 {
+            0.0
+        } else if (h2 - h1).abs() <= 180.0 {
+            h2 - h1
+        } else if h2 - h1 > 180.0 {
+            h2 - h1 - 360.0
+        } else {
+            h2 - h1 + 360.0
+        };
+        let dh = 2.0 * (c1 * c2).sqrt() * (dh.to_radians() / 2.0).sin();
+
+        let l_bar = (self.l + other.l) / 2.0;
+        let c_bar = (c1 + c2) / 2.0;
+        let h_bar = if c1 * c2 == 0.0 {
+            h1 + h2
+        } else if (h1 - h2).abs() <= 180.0 {
+            (h1 + h2) / 2.0
+        } else if h1 + h2 < 360.0 {
+            (h1 + h2 + 360.0) / 2.0
+        } else {
+            (h1 + h2 - 360.0) / 2.0
+        };
+
+        let t = 1.0 - 0.17 * (h_bar - 30.0).to_radians().cos()
+            + 0.24 * (2.0 * h_bar).to_radians().cos()
+            + 0.32 * (3.0 * h_bar + 6.0).to_radians().cos()
+            - 0.20 * (4.0 * h_bar - 63.0).to_radians().cos();
+        let l50 = (l_bar - 50.0).powi(2);
+        let sl = 1.0 + 0.015 * l50 / (20.0 + l50).sqrt();
+        let sc = 1.0 + 0.045 * c_bar;
+        let sh = 1.0 + 0.015 * c_bar * t;
+        let c_bar7 = c_bar.powi(7);
+        let rc = 2.0 * (c_bar7 / (c_bar7 + 25f32.powi(7))).sqrt();
+        let d_theta = 30.0 * (-((h_bar - 275.0) / 25.0).powi(2)).exp();
+        let rt = -(2.0 * d_theta * PI / 180.0).sin() * rc;
+
+        let (l, c, h) = (dl / sl, dc / sc, dh / sh);
+        (l * l + c * c + h * h + rt * c * h).sqrt()
+    }
+}
+
+/// CIEDE2000 difference between two hex colors.
+pub fn delta_e(a: &str, b: &str) -> Option<f32> {
+    Some(Lab::from_hex(a)?.delta_e(Lab::from_hex(b)?))
+}
+
+fn srgb_to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> u8 {
+    let c = c.clamp(0.0, 1.0);
+    let c = if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (c * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_to_rgb() {
+        assert_eq!(hex_to_rgb("#A1B2C3"), Some((0xA1, 0xB2, 0xC3)));
+        assert_eq!(hex_to_rgb("#000000"), Some((0, 0, 0)));
+        assert_eq!(hex_to_rgb("123456"), None);
+        assert_eq!(hex_to_rgb("#ffff"), None);
+        assert_eq!(hex_to_rgb("#aébcd"), None);
+        assert_eq!(hex_to_rgb("#+1+2+3"), None);
+    }
+
+    #[test]
+    fn oklab_round_trips_hex() {
+        for hex in ["#000000", "#FFFFFF", "#2E73FF", "#CC241D", "#8EC07C"] {
+            assert_eq!(Oklab::from_hex(hex).unwrap().to_hex(), hex);
+        }
+    }
+
+    #[test]
+    fn computes_wcag_contrast() {
+        for (a, b, expected) in [
+            ("#000000", "#FFFFFF", 21.0),
+            ("#777777", "#777777", 1.0),
+            ("#FFFFFF", "#767676", 4.54),
+        ] {
+            let ratio = contrast_ratio(a, b).unwrap();
+            assert!((ratio - expected).abs() < 0.01, "{a} on {b}: {ratio}");
+        }
+    }
+
+    #[test]
+    fn ciede2000_matches_reference_pairs() {
+        // From Sharma, Wu & Dalal's CIEDE2000 test data.
+        let pairs = [
+            ((50.0, 2.6772, -79.7751), (50.0, 0.0, -82.7485), 2.0425),
+            ((50.0, 2.5, 0.0), (73.0, 25.0, -18.0), 27.1492),
+            (
+                (60.2574, -34.0099, 36.2677),
+                (60.4626, -34.1751, 39.4387),
+                1.2644,
+            ),
+        ];
+        for ((l1, a1, b1), (l2, a2, b2), expected) in pairs {
+            let lhs = Lab {
+                l: l1,
+                a: a1,
+                b: b1,
+            };
+            let rhs = Lab {
+                l: l2,
+                a: a2,
+                b: b2,
+            };
+            let de = lhs.delta_e(rhs);
+            assert!((de - expected).abs() < 1e-3, "{de} != {expected}");
+        }
+
+        assert_eq!(delta_e("#1E1E1E", "#1E1E1E"), Some(0.0));
+        assert!(delta_e("#1E1E1E", "#1F1E1E").unwrap() < 1.0);
+        assert!(delta_e("#FFFFFF", "#000000").unwrap() > 99.0);
+    }
+}
@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use anyhow::{Result, bail};
+
+use veneer_theme::{
+    Palette,
+    graph::RefGraph,
+    template::{self, reads_key},
+};
+
+pub fn run(palette_path: &Path, path: &str, templates: Option<&Path>) -> Result<()> {
+    let palette = Palette::load(palette_path)?;
+    let graph = RefGraph::new(&palette);
+    if !graph.contains(path) {
+        bail!("unknown palette key '{path}'");
+    }
+
+    let dependents = graph.dependents(path);
+    if dependents.is_empty() {
+        println!("No keys depend on {path}.");
+    } else {
+        println!("Keys depending on {path}:");
+        for key in &dependents {
+            println!("  {key}");
+        }
+    }
+
+    let Some(src) = templates else {
+        return Ok(());
+    };
+    let affected: Vec<&str> = std::iter::once(path).chain(dependents).collect();
+
+    let mut hits = Vec::new();
+    for template in template::scan(src)? {
+        let used: Vec<&str> = affected
+            .iter()
+            .copied()
+            .filter(|key| template.reads.iter().any(|read| reads_key(read, key)))
+            .collect();
+        if !used.is_empty() {
+            hits.push((template.path, used));
+        }
+    }
+
+    println!();
+    if hits.is_empty() {
+        println!("No templates under {} read these keys.", src.display());
+        return Ok(());
+    }
+    println!("Templates reading these keys:");
+    for (template_path, used) in hits {
+        println!("  {} ({})", template_path.display(), used.join(", "));
+    }
+    Ok(())
+}
@@ -0,0 +1,200 @@
+//! Differences between two resolved palettes, for reviewing palette changes.
+
+use serde::Serialize;
+
+use crate::{
+    ResolvedPalette,
+    color::{contrast_ratio, delta_e},
+    palette::Tone,
+};
+
+/// WCAG contrast levels a color can fall below: AAA text, AA text and AA
+/// large text / UI components.
+const CONTRAST_LEVELS: [f32; 3] = [7.0, 4.5, 3.0];
+
+/// One key that differs between the palettes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Change {
+    Added {
+        key: String,
+        hex: String,
+    },
+    Removed {
+        key: String,
+        hex: String,
+    },
+    Changed {
+        key: String,
+        old: String,
+        new: String,
+        /// CIEDE2000 difference between `old` and `new`.
+        delta_e: f32,
+    },
+}
+
+impl Change {
+    /// `added`, `removed` or `changed`, as in the JSON output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Change::Added { .. } => "added",
+            Change::Removed { .. } => "removed",
+            Change::Changed { .. } => "changed",
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        match self {
+            Change::Added { key, .. }
+            | Change::Removed { key, .. }
+            | Change::Changed { key, .. } => key,
+        }
+    }
+}
+
+/// A key whose contrast against its tone's background fell below a WCAG
+/// level it used to meet.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContrastRegression {
+    pub key: String,
+    /// The background it is measured against, e.g. `colors.dark.background`.
+    pub background: String,
+    pub old: f32,
+    pub new: f32,
+    /// Highest level `old` met and `new` no longer does.
+    pub level: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PaletteDiff {
+    /// Added, removed and changed keys, sorted by key.
+    pub changes: Vec<Change>,
+    pub contrast: Vec<ContrastRegression>,
+}
+
+impl PaletteDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.contrast.is_empty()
+    }
+}
+
+/// Compare every color key of two palettes.
+pub fn diff(old: &ResolvedPalette, new: &ResolvedPalette) -> PaletteDiff {
+    let mut changes = Vec::new();
+    for (key, trace) in &old.trace {
+        match new.trace.get(key) {
+            None => changes.push(Change::Removed {
+                key: key.clone(),
+                hex: trace.hex.clone(),
+            }),
+            Some(now) if now.hex != trace.hex => changes.push(Change::Changed {
+                key: key.clone(),
+                old: trace.hex.clone(),
+                new: now.hex.clone(),
+                delta_e: delta_e(&trace.hex, &now.hex).unwrap_or_default(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (key, trace) in &new.trace {
+        if !old.trace.contains_key(key) {
+            changes.push(Change::Added {
+                key: key.clone(),
+                hex: trace.hex.clone(),
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.key().cmp(b.key()));
+
+    PaletteDiff {
+        changes,
+        contrast: contrast_regressions(old, new),
+    }
+}
+
+/// Keys in both palettes whose contrast on their tone's background dropped
+/// below a level in [`CONTRAST_LEVELS`]. Keys belong to a tone as given by
+/// [`Tone::of_key`].
+fn contrast_regressions(old: &ResolvedPalette, new: &ResolvedPalette) -> Vec<ContrastRegression> {
+    let mut out = Vec::new();
+    for tone in Tone::ALL {
+        let background = format!("colors.{tone}.background");
+        let (Some(old_bg), Some(new_bg)) = (old.trace.get(&background), new.trace.get(&background))
+        else {
+            continue;
+        };
+
+        for (key, trace) in &old.trace {
+            if *key == background || Tone::of_key(key) != Some(tone) {
+                continue;
+            }
+            let Some(now) = new.trace.get(key) else {
+                continue;
+            };
+            let (Some(before), Some(after)) = (
+                contrast_ratio(&trace.hex, &old_bg.hex),
+                contrast_ratio(&now.hex, &new_bg.hex),
+            ) else {
+                continue;
+            };
+            if let Some(level) = CONTRAST_LEVELS
+                .into_iter()
+                .find(|level| before >= *level && after < *level)
+            {
+                out.push(ContrastRegression {
+                    key: key.clone(),
+                    background: background.clone(),
+                    old: before,
+                    new: after,
+                    level,
+                });
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Palette, palette::TEST_PALETTE};
+
+    #[test]
+    fn reports_changes_and_contrast_regressions() {
+        let old = TEST_PALETTE.replace(
+            "text = \"#E6EDF3\"",
+            "text = \"#E6EDF3\"\ngone = \"#123456\"",
+        );
+        let new = old
+            .replace("muted = \"#6A737D\"", "muted = \"#9AA3AD\"")
+            .replace("gone = \"#123456\"", "link = \"colors.light.muted\"")
+            .replace("info = \"#3FA7D6\"", "info = \"#3FA7D7\"");
+        let old = Palette::parse(&old).unwrap().resolve().unwrap();
+        let new = Palette::parse(&new).unwrap().resolve().unwrap();
+
+        let result = diff(&old, &new);
+        let keys: Vec<_> = result.changes.iter().map(Change::key).collect();
+        assert_eq!(
+            keys,
+            [
+                "accents.info",
+                "colors.dark.gone",
+                "colors.dark.link",
+                "colors.light.muted"
+            ]
+        );
+        let Change::Changed { delta_e, .. } = &result.changes[0] else {
+            panic!("{:?}", result.changes[0]);
+        };
+        assert!(*delta_e < 1.0);
+        assert!(matches!(&result.changes[2], Change::Added { hex, .. } if hex == "#9AA3AD"));
+
+        assert_eq!(result.contrast.len(), 1);
+        let regression = &result.contrast[0];
+        assert_eq!(regression.key, "colors.light.muted");
+        assert_eq!(regression.level, 4.5);
+        assert!(regression.old >= 4.5 && regression.new < 3.0);
+
+        assert!(diff(&old, &old).is_empty());
+    }
+}
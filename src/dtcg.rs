@@ -0,0 +1,405 @@
+//! W3C Design Tokens Community Group (DTCG) JSON export and import.
+//!
+//! Each palette section becomes a top-level group with `"$type": "color"`;
+//! literal colors are exported as hex and references as `{path}` aliases, so
+//! references survive a round trip. `meta` and `vars` have no DTCG
+//! equivalent and travel under `$extensions.veneer`.
+//!
+//! Tonal scales and `derive` don't survive: a scale is exported as its step
+//! colors and a deriving tone as the literals it derived, so an imported
+//! palette resolves to the same colors but writes them out one by one.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+use crate::{
+    Error, Result,
+    export::strip_nulls,
+    palette::{ColorNode, ColorRef, ColorTree, Palette, TokenNode, validate_palette},
+};
+
+/// Key under `$extensions` holding veneer-specific data.
+const EXTENSION: &str = "veneer";
+
+/// Export a palette as a DTCG token document, keeping references as aliases.
+/// Scale steps and derived colors are exported as plain tokens.
+pub fn export(palette: &Palette) -> Result<Value> {
+    let mut root = Map::new();
+    let mut extension = Map::new();
+    extension.insert("meta".into(), to_json(&palette.meta)?);
+    if !palette.vars.is_empty() {
+        extension.insert("vars".into(), to_json(&palette.vars)?);
+    }
+    root.insert("$extensions".into(), json!({ EXTENSION: extension }));
+
+    let mut colors = Map::new();
+    colors.insert(
+        "light".into(),
+        Value::Object(export_tree(&palette.colors.light)),
+    );
+    colors.insert(
+        "dark".into(),
+        Value::Object(export_tree(&palette.colors.dark)),
+    );
+    root.insert("colors".into(), color_group(colors));
+    root.insert("accents".into(), color_group(export_tree(&palette.accents)));
+
+    let mut ansi = Map::new();
+    for (tone, scheme) in [("light", &palette.ansi.light), ("dark", &palette.ansi.dark)] {
+        let mut levels = Map::new();
+        for (level, row) in [("normal", &scheme.normal), ("bright", &scheme.bright)] {
+            let mut colors = Map::new();
+            for (name, cref) in row.entries() {
+                colors.insert(name.into(), export_ref(cref));
+            }
+            levels.insert(level.into(), Value::Object(colors));
+        }
+        ansi.insert(tone.into(), Value::Object(levels));
+    }
+    root.insert("ansi".into(), color_group(ansi));
+
+    if !palette.tokens.is_empty() {
+        root.insert("tokens".into(), color_group(export_tokens(&palette.tokens)));
+    }
+    for (name, tree) in &palette.groups {
+        root.insert(name.clone(), color_group(export_tree(tree)));
+    }
+
+    Ok(Value::Object(root))
+}
+
+/// Build a palette from a DTCG token document.
+pub fn import(raw: &str) -> Result<Palette> {
+    let doc: Value = serde_json::from_str(raw).map_err(Error::Json)?;
+    let Value::Object(root) = doc else {
+        return Err(Error::InvalidDtcg("document must be a JSON object".into()));
+    };
+
+    let extension = root
+        .get("$extensions")
+        .and_then(|ext| ext.get(EXTENSION))
+        .cloned()
+        .unwrap_or_default();
+
+    let mut palette = Map::new();
+    palette.insert(
+        "meta".into(),
+        extension
+            .get("meta")
+            .cloned()
+            .unwrap_or_else(|| json!({ "name": "Imported" })),
+    );
+    if let Some(vars) = extension.get("vars") {
+        palette.insert("vars".into(), vars.clone());
+    }
+    for (key, value) in &root {
+        if key.starts_with('$') {
+            continue;
+        }
+        palette.insert(key.clone(), import_group(key, value, None)?);
+    }
+
+    let palette: Palette = serde_json::from_value(Value::Object(palette)).map_err(Error::Json)?;
+    validate_palette(&palette)?;
+    Ok(palette)
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Value> {
+    let mut value = serde_json::to_value(value).map_err(|source| Error::Serialize {
+        format: "json",
+        source: Box::new(source),
+    })?;
+    strip_nulls(&mut value);
+    Ok(value)
+}
+
+fn color_group(children: Map<String, Value>) -> Value {
+    let mut group = Map::new();
+    group.insert("$type".into(), Value::from("color"));
+    group.extend(children);
+    Value::Object(group)
+}
+
+fn export_tree(tree: &ColorTree) -> Map<String, Value> {
+    tree.iter()
+        .map(|(k, node)| {
+            let value = match node {
+                ColorNode::Color(cref) => export_ref(cref),
+                ColorNode::Scale(scale) => Value::Object(
+                    scale
+                        .colors
+                        .iter()
+                        .map(|(step, cref)| (step.clone(), export_ref(cref)))
+                        .collect(),
+                ),
+                ColorNode::Group(children) => Value::Object(export_tree(children)),
+            };
+            (k.clone(), value)
+        })
+        .collect()
+}
+
+fn export_tokens(nodes: &BTreeMap<String, TokenNode>) -> Map<String, Value> {
+    nodes
+        .iter()
+        .map(|(k, node)| {
+            let value = match node {
+                TokenNode::Token(token) => json!({
+                    "light": export_ref(&token.light),
+                    "dark": export_ref(&token.dark),
+                }),
+                TokenNode::Group(children) => Value::Object(export_tokens(children)),
+            };
+            (k.clone(), value)
+        })
+        .collect()
+}
+
+fn export_ref(cref: &ColorRef) -> Value {
+    let value = match cref {
+        ColorRef::Hex(hex) => hex.clone(),
+        ColorRef::Path(path) => format!("{{{path}}}"),
+    };
+    json!({ "$value": value })
+}
+
+/// Strip DTCG token wrappers, leaving the plain tree a palette deserializes from.
+///
+/// `kind` is the `$type` inherited from the nearest enclosing group that sets
+/// one; a token's own `$type` takes precedence.
+fn import_group(path: &str, value: &Value, kind: Option<&str>) -> Result<Value> {
+    let Value::Object(map) = value else {
+        return Err(Error::InvalidDtcg(format!(
+            "{path} must be a group or a token"
+        )));
+    };
+
+    let kind = match map.get("$type") {
+        Some(Value::String(own)) => Some(own.as_str()),
+        Some(_) => {
+            return Err(Error::InvalidDtcg(format!("{path} has a non-string $type")));
+        }
+        None => kind,
+    };
+
+    if let Some(token) = map.get("$value") {
+        return import_value(path, kind, token);
+    }
+
+    let mut out = Map::new();
+    for (key, child) in map {
+        if key.starts_with('$') {
+            continue;
+        }
+        out.insert(
+            key.clone(),
+            import_group(&format!("{path}.{key}"), child, kind)?,
+        );
+    }
+    Ok(Value::Object(out))
+}
+
+fn import_value(path: &str, kind: Option<&str>, value: &Value) -> Result<Value> {
+    match kind {
+        None | Some("color") => {}
+        Some(other) => {
+            return Err(Error::InvalidDtcg(format!(
+                "{path} has unsupported $type '{other}'; only color tokens can be imported"
+            )));
+        }
+    }
+
+    let s = match value {
+        Value::String(s) => s.as_str(),
+        // DTCG 2025 color objects carry a `hex` fallback.
+        Value::Object(color) => color
+            .get("hex")
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::InvalidDtcg(format!("{path} color object has no hex value")))?,
+        _ => {
+            return Err(Error::InvalidDtcg(format!(
+                "{path} must be a hex string or an alias"
+            )));
+        }
+    };
+
+    let s = match s.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+        Some(alias) => alias.to_string(),
+        None => s.to_string(),
+    };
+    Ok(Value::String(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::TEST_PALETTE;
+
+    const PALETTE: &str = r##"
+[meta]
+name = "Tokens"
+version = "1.0.0"
+
+[colors.light]
+background = "#FFFFFF"
+primary = "#2E73FF"
+
+[colors.dark]
+background = "#0E1117"
+primary = "colors.light.primary"
+
+[accents]
+info = "#3FA7D6"
+
+[tokens]
+syntax.comment = { light = "colors.light.primary", dark = "#EEEEEE" }
+
+[vars]
+ui_font = "Inter"
+
+[ui.sidebar]
+background = "colors.dark.background"
+
+[ansi.light.normal]
+black="#000000"
+red="#000000"
+green="#000000"
+yellow="#000000"
+blue="#000000"
+magenta="#000000"
+cyan="#000000"
+white="colors.light.background"
+
+[ansi.light.bright]
+black="#111111"
+red="#111111"
+green="#111111"
+yellow="#111111"
+blue="#111111"
+magenta="#111111"
+cyan="#111111"
+white="#111111"
+
+[ansi.dark.normal]
+black="#000000"
+red="#000000"
+green="#000000"
+yellow="#000000"
+blue="#000000"
+magenta="#000000"
+cyan="#000000"
+white="#000000"
+
+[ansi.dark.bright]
+black="#111111"
+red="#111111"
+green="#111111"
+yellow="#111111"
+blue="#111111"
+magenta="#111111"
+cyan="#111111"
+white="#111111"
+"##;
+
+    #[test]
+    fn exports_references_as_aliases() {
+        let doc = export(&Palette::parse(PALETTE).unwrap()).unwrap();
+
+        assert_eq!(doc["colors"]["$type"], "color");
+        assert_eq!(
+            doc["colors"]["dark"]["primary"]["$value"],
+            "{colors.light.primary}"
+        );
+        assert_eq!(doc["colors"]["light"]["primary"]["$value"], "#2E73FF");
+        assert_eq!(
+            doc["ansi"]["light"]["normal"]["white"]["$value"],
+            "{colors.light.background}"
+        );
+        assert_eq!(
+            doc["tokens"]["syntax"]["comment"]["dark"]["$value"],
+            "#EEEEEE"
+        );
+        assert_eq!(
+            doc["ui"]["sidebar"]["background"]["$value"],
+            "{colors.dark.background}"
+        );
+        assert_eq!(doc["$extensions"]["veneer"]["meta"]["name"], "Tokens");
+        assert_eq!(doc["$extensions"]["veneer"]["vars"]["ui_font"], "Inter");
+    }
+
+    #[test]
+    fn round_trips_through_import() {
+        let original = Palette::parse(PALETTE).unwrap();
+        let doc = export(&original).unwrap();
+        let imported = import(&doc.to_string()).unwrap();
+
+        let before = crate::export::to_value(&original.resolve().unwrap(), None).unwrap();
+        let after = crate::export::to_value(&imported.resolve().unwrap(), None).unwrap();
+        assert_eq!(before, after);
+        assert!(matches!(
+            &imported.colors.dark["primary"],
+            ColorNode::Color(ColorRef::Path(p)) if p == "colors.light.primary"
+        ));
+    }
+
+    #[test]
+    fn writes_scales_and_derived_colors_out_as_literals() {
+        let start = TEST_PALETTE.find("[colors.dark]").unwrap();
+        let end = TEST_PALETTE.find("[accents]").unwrap();
+        let raw = format!(
+            "{}[colors.dark]\nderive = \"light\"\n\n{}",
+            &TEST_PALETTE[..start],
+            &TEST_PALETTE[end..]
+        );
+        let original = Palette::parse(&raw).unwrap();
+        let doc = export(&original).unwrap();
+        assert!(doc["colors"]["light"]["blue"]["900"]["$value"].is_string());
+        assert!(doc["colors"]["dark"]["text"]["$value"].is_string());
+
+        let imported = import(&doc.to_string()).unwrap();
+        assert!(matches!(
+            &imported.colors.light["blue"],
+            ColorNode::Group(steps) if steps.contains_key("900")
+        ));
+        assert!(!imported.colors.dark.contains_key("derive"));
+        assert!(imported.colors.derived.is_empty());
+        let before = crate::export::to_value(&original.resolve().unwrap(), None).unwrap();
+        let after = crate::export::to_value(&imported.resolve().unwrap(), None).unwrap();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn rejects_non_color_tokens() {
+        let doc = r#"{ "spacing": { "small": { "$type": "dimension", "$value": "4px" } } }"#;
+        let err = import(doc).unwrap_err();
+        assert!(
+            err.to_string().contains("unsupported $type 'dimension'"),
+            "unexpected error: {err}"
+        );
+
+        let doc = r#"{ "spacing": { "$type": "dimension", "small": { "$value": "4px" } } }"#;
+        let err = import(doc).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("spacing.small has unsupported $type 'dimension'"),
+            "group $type is inherited: {err}"
+        );
+
+        let mut doc = export(&Palette::parse(PALETTE).unwrap()).unwrap();
+        doc["ui"]["$type"] = "dimension".into();
+        doc["ui"]["sidebar"]["background"]["$type"] = "color".into();
+        let palette = import(&doc.to_string()).unwrap();
+        assert!(
+            matches!(
+                &palette.groups["ui"]["sidebar"],
+                ColorNode::Group(sidebar) if matches!(
+                    &sidebar["background"],
+                    ColorNode::Color(ColorRef::Path(p)) if p == "colors.dark.background"
+                )
+            ),
+            "a token's own $type overrides its group's"
+        );
+    }
+}
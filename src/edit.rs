@@ -0,0 +1,364 @@
+//! Format-preserving edits to palette TOML: comments, ordering and layout
+//! of untouched entries stay as written.
+
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use toml_edit::{DocumentMut, Item, Key, Table, Value};
+
+use crate::{
+    Error, Palette, Result,
+    palette::{ANSI_NAMES, ColorRef},
+};
+
+/// Top-level tables whose key order is kept as written.
+const UNSORTED_TABLES: [&str; 2] = ["meta", "vars"];
+
+/// A palette file opened for editing.
+#[derive(Debug, Clone)]
+pub struct PaletteDocument {
+    doc: DocumentMut,
+}
+
+impl PaletteDocument {
+    /// Value at a dotted path (`colors.dark.background`), if it's a string.
+    pub fn get(&self, path: &str) -> Option<&str> {
+        let mut item = self.doc.as_item();
+        for segment in path.split('.') {
+            item = item.get(segment)?;
+        }
+        item.as_str()
+    }
+
+    /// Replace the color at `path`, keeping the comments and spacing around it.
+    pub fn set_color(&mut self, path: &str, value: &ColorRef) -> Result<()> {
+        self.set_str(path, value.as_str())
+    }
+
+    /// Replace the value at `path` with a string, keeping the comments and
+    /// spacing around it.
+    pub fn set_str(&mut self, path: &str, value: &str) -> Result<()> {
+        let mut item = self.doc.as_item_mut();
+        for segment in path.split('.') {
+            item = item
+                .get_mut(segment)
+                .filter(|child| !child.is_none())
+                .ok_or_else(|| Error::KeyNotFound(path.to_string()))?;
+        }
+        let Some(old) = item.as_value() else {
+            return Err(Error::KeyNotFound(path.to_string()));
+        };
+
+        let mut new = Value::from(value);
+        *new.decor_mut() = old.decor().clone();
+        *item = Item::Value(new);
+        Ok(())
+    }
+
+    /// Add a string at `path`, creating missing parent tables. Fails if a
+    /// parent is not a table or the key already exists.
+    pub fn insert_color(&mut self, path: &str, value: &ColorRef) -> Result<()> {
+        let not_found = || Error::KeyNotFound(path.to_string());
+        let (parents, key) = path.rsplit_once('.').ok_or_else(not_found)?;
+        let mut item = self.doc.as_item_mut();
+        for segment in parents.split('.') {
+            let table = item.as_table_like_mut().ok_or_else(not_found)?;
+            if table.get(segment).is_none() {
+                table.insert(segment, toml_edit::table());
+            }
+            item = table.get_mut(segment).ok_or_else(not_found)?;
+        }
+
+        let table = item.as_table_like_mut().ok_or_else(not_found)?;
+        if table.contains_key(key) {
+            return Err(Error::KeyExists(path.to_string()));
+        }
+        table.insert(key, Item::Value(Value::from(value.as_str())));
+        Ok(())
+    }
+}
+
+impl PaletteDocument {
+    /// Rewrite in canonical form: uppercase hex, and keys sorted within each
+    /// table (`derive` first, ANSI rows in index order) and aligned on `=`.
+    /// `[meta]` and `[vars]` are left as written. Comments stay attached to
+    /// the key they precede or follow.
+    pub fn format(&mut self) {
+        for (key, item) in self.doc.iter_mut() {
+            if let Item::Table(table) = item {
+                let order = match key.get() {
+                    "ansi" => KeyOrder::Ansi,
+                    name if UNSORTED_TABLES.contains(&name) => KeyOrder::AsWritten,
+                    _ => KeyOrder::Sorted,
+                };
+                format_table(table, order);
+            }
+        }
+    }
+}
+
+/// Set the color at `path` in palette TOML `raw` to `value` (hex or a
+/// reference), adding the key if it's missing. Returns the edited TOML once
+/// it parses and resolves, so bad hex, missing targets and cycles are
+/// rejected before anything is written.
+pub fn set_color(raw: &str, path: &str, value: &str) -> Result<String> {
+    let value = if value.starts_with('#') {
+        ColorRef::Hex(value.to_uppercase())
+    } else {
+        ColorRef::Path(value.to_string())
+    };
+    let mut doc: PaletteDocument = raw.parse()?;
+    match doc.set_color(path, &value) {
+        Err(Error::KeyNotFound(_)) => doc.insert_color(path, &value)?,
+        other => other?,
+    }
+
+    let edited = doc.to_string();
+    Palette::parse(&edited)?.resolve()?;
+    Ok(edited)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum KeyOrder {
+    /// Left as written: order, spacing and values.
+    AsWritten,
+    Sorted,
+    /// ANSI rows: index order.
+    Ansi,
+}
+
+fn format_table(table: &mut Table, order: KeyOrder) {
+    match order {
+        // Free-form tables, values included.
+        KeyOrder::AsWritten => return,
+        KeyOrder::Sorted => table.sort_values_by(|a, _, b, _| key_order(a, b)),
+        KeyOrder::Ansi => table.sort_values_by(|a, _, b, _| ansi_index(a).cmp(&ansi_index(b))),
+    }
+
+    let width = table
+        .iter()
+        .filter(|(_, item)| item.is_value())
+        .filter_map(|(key, _)| table.key(key))
+        .map(|key| key.display_repr().chars().count())
+        .max()
+        .unwrap_or(0);
+    for (mut key, item) in table.iter_mut() {
+        match item {
+            Item::Value(value) => {
+                // Columns the key takes as written, quotes included.
+                let pad = width - key.display_repr().chars().count() + 1;
+                key.leaf_decor_mut().set_suffix(" ".repeat(pad));
+                value.decor_mut().set_prefix(" ");
+                format_value(value);
+            }
+            Item::Table(child) => format_table(child, order),
+            _ => {}
+        }
+    }
+}
+
+fn format_value(value: &mut Value) {
+    match value {
+        Value::String(s)
+            if is_hex(s.value()) && s.value().chars().any(|c| c.is_ascii_lowercase()) =>
+        {
+            let decor = s.decor().clone();
+            *value = Value::from(s.value().to_uppercase());
+            *value.decor_mut() = decor;
+        }
+        Value::InlineTable(table) => table.iter_mut().for_each(|(_, v)| format_value(v)),
+        _ => {}
+    }
+}
+
+fn is_hex(s: &str) -> bool {
+    s.len() == 7 && s.starts_with('#') && s[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn ansi_index(key: &Key) -> usize {
+    ANSI_NAMES
+        .iter()
+        .position(|name| *name == key.get())
+        .unwrap_or(ANSI_NAMES.len())
+}
+
+fn key_order(a: &Key, b: &Key) -> Ordering {
+    (a.get() != "derive", a.get()).cmp(&(b.get() != "derive", b.get()))
+}
+
+impl FromStr for PaletteDocument {
+    type Err = Error;
+
+    fn from_str(raw: &str) -> Result<Self> {
+        Ok(Self {
+            doc: raw.parse().map_err(Error::EditToml)?,
+        })
+    }
+}
+
+impl fmt::Display for PaletteDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.doc.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::TEST_PALETTE;
+
+    #[test]
+    fn replaces_values_and_keeps_comments() {
+        let raw = r##"# Demo
+[colors.dark]
+background = "#0E1117"   # base
+surface = "#0E1117"
+
+[tokens]
+syntax.comment = { light = "#111111", dark = "#EEEEEE" }
+"##;
+        let mut doc: PaletteDocument = raw.parse().unwrap();
+        doc.set_color(
+            "colors.dark.surface",
+            &ColorRef::Path("colors.dark.background".into()),
+        )
+        .unwrap();
+        doc.set_color(
+            "tokens.syntax.comment.dark",
+            &ColorRef::Hex("#DDDDDD".into()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            doc.to_string(),
+            r##"# Demo
+[colors.dark]
+background = "#0E1117"   # base
+surface = "colors.dark.background"
+
+[tokens]
+syntax.comment = { light = "#111111", dark = "#DDDDDD" }
+"##
+        );
+        assert_eq!(doc.get("colors.dark.background"), Some("#0E1117"));
+
+        let err = doc
+            .set_color("colors.dark.nope", &ColorRef::Hex("#000000".into()))
+            .unwrap_err();
+        assert!(matches!(err, Error::KeyNotFound(p) if p == "colors.dark.nope"));
+    }
+
+    #[test]
+    fn inserts_new_keys_and_tables() {
+        let raw = "[colors.dark]\nderive = \"light\" # rest from light\nbackground = \"#0E1117\"\n";
+        let mut doc: PaletteDocument = raw.parse().unwrap();
+        doc.insert_color("colors.dark.text", &ColorRef::Hex("#A8ADB4".into()))
+            .unwrap();
+        doc.insert_color(
+            "colors.dark.ui.panel",
+            &ColorRef::Path("colors.dark.background".into()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            doc.to_string(),
+            r##"[colors.dark]
+derive = "light" # rest from light
+background = "#0E1117"
+text = "#A8ADB4"
+
+[colors.dark.ui]
+panel = "colors.dark.background"
+"##
+        );
+        let err = doc
+            .insert_color("colors.dark.text", &ColorRef::Hex("#000000".into()))
+            .unwrap_err();
+        assert!(matches!(err, Error::KeyExists(p) if p == "colors.dark.text"));
+    }
+
+    #[test]
+    fn formats_into_canonical_form() {
+        let raw = r##"[meta]
+name = "Demo"
+author="jane"
+
+[vars]
+accent_hex = "#ff00aa"
+
+[colors.dark]
+text= "#e6edf3"   # body text
+# Comments travel with their key.
+background = "#0e1117"
+derive = "light"
+
+[ansi.dark.normal]
+white = "#ffffff"
+black  =  "colors.dark.background"
+magenta = "#B16286"
+
+[ui]
+"side.bar" = "#111111"
+"café"="#222222"
+border = "#333333"
+
+[tokens]
+syntax.keyword = { light = "#aa0000", dark = "#ff5555" }
+syntax.comment = { light = "#111111", dark = "#EEEEEE" }
+"##;
+        let mut doc: PaletteDocument = raw.parse().unwrap();
+        doc.format();
+        let formatted = doc.to_string();
+        assert_eq!(
+            formatted,
+            r##"[meta]
+name = "Demo"
+author="jane"
+
+[vars]
+accent_hex = "#ff00aa"
+
+[colors.dark]
+derive     = "light"
+# Comments travel with their key.
+background = "#0E1117"
+text       = "#E6EDF3"   # body text
+
+[ansi.dark.normal]
+black   = "colors.dark.background"
+magenta = "#B16286"
+white   = "#FFFFFF"
+
+[ui]
+border     = "#333333"
+"café"     = "#222222"
+"side.bar" = "#111111"
+
+[tokens]
+syntax.comment = { light = "#111111", dark = "#EEEEEE" }
+syntax.keyword = { light = "#AA0000", dark = "#FF5555" }
+"##
+        );
+
+        let mut again: PaletteDocument = formatted.parse().unwrap();
+        again.format();
+        assert_eq!(again.to_string(), formatted);
+    }
+
+    #[test]
+    fn sets_colors_only_when_the_palette_still_resolves() {
+        let raw = TEST_PALETTE;
+        let edited = set_color(raw, "colors.dark.primary", "#3b82f6").unwrap();
+        assert!(edited.contains("primary = \"#3B82F6\"  # reference to another key"));
+        let edited = set_color(&edited, "accents.link", "colors.dark.primary").unwrap();
+        let resolved = Palette::parse(&edited).unwrap().resolve().unwrap();
+        assert_eq!(resolved.trace["accents.link"].hex, "#3B82F6");
+
+        let cycle = set_color(raw, "colors.light.primary", "colors.dark.primary").unwrap_err();
+        assert!(
+            matches!(&cycle, Error::Resolve { source, .. } if matches!(**source, Error::Cycle(_))),
+            "{cycle:?}"
+        );
+        assert!(set_color(raw, "colors.light.text", "#12345").is_err());
+        assert!(set_color(raw, "colors.light.text", "colors.light.nope").is_err());
+    }
+}
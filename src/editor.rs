@@ -0,0 +1,694 @@
+//! `veneer edit`: browse the palette's colors, adjust them in OKLCH with live
+//! contrast and previews, and save back to the TOML file.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    mem,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::Print,
+    terminal::{self, ClearType},
+};
+use veneer_theme::{
+    Palette, ResolvedPalette,
+    color::{Oklch, contrast_ratio},
+    edit,
+    palette::{ColorRef, Tone},
+};
+
+use crate::{
+    preview::{self, Scene, SceneColors},
+    show::swatch,
+};
+
+/// How far one key press moves each OKLCH channel.
+const LIGHTNESS_STEP: f32 = 0.01;
+const CHROMA_STEP: f32 = 0.005;
+const HUE_STEP: f32 = 3.0;
+/// Chroma at the right end of the slider, about the most sRGB reaches.
+const MAX_CHROMA: f32 = 0.37;
+const SLIDER_WIDTH: usize = 36;
+const PREVIEW_WIDTH: usize = 44;
+/// Screen rows used by everything but the key list.
+const CHROME_ROWS: usize = 32;
+
+pub fn run(palette_path: &Path) -> Result<()> {
+    let mut editor = Editor::open(palette_path)?;
+    let _screen = Screen::enter()?;
+    let mut out = io::stdout();
+    loop {
+        editor.draw(&mut out)?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !editor.handle(key)? {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Raw mode on the alternate screen, restored when dropped, including on
+/// errors.
+struct Screen;
+
+impl Screen {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Keys written in the palette file under one table, e.g. `colors.dark`.
+struct Section {
+    title: String,
+    keys: Vec<String>,
+}
+
+enum Mode {
+    Browse,
+    /// Typing the key (or hex) the selected key should point to.
+    Reference(String),
+}
+
+struct Editor {
+    path: PathBuf,
+    raw: String,
+    /// Contents as last read or saved.
+    saved: String,
+    /// Earlier versions of `raw`, for undo.
+    history: Vec<String>,
+    resolved: ResolvedPalette,
+    /// Values as written, by key.
+    entries: BTreeMap<String, ColorRef>,
+    sections: Vec<Section>,
+    section: usize,
+    row: usize,
+    /// The color being adjusted, kept unrounded between key presses so
+    /// repeated steps don't drift through hex rounding.
+    working: Option<Oklch>,
+    preview: Tone,
+    mode: Mode,
+    message: String,
+    confirm_quit: bool,
+}
+
+impl Editor {
+    fn open(path: &Path) -> Result<Self> {
+        let raw =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::new(path, raw)
+    }
+
+    /// An editor for `raw`, saved to `path`.
+    fn new(path: &Path, raw: String) -> Result<Self> {
+        let palette =
+            Palette::parse(&raw).with_context(|| format!("parsing {}", path.display()))?;
+        let mut editor = Self {
+            path: path.to_path_buf(),
+            saved: raw.clone(),
+            raw,
+            history: Vec::new(),
+            resolved: palette.resolve()?,
+            entries: BTreeMap::new(),
+            sections: Vec::new(),
+            section: 0,
+            row: 0,
+            working: None,
+            preview: Tone::Dark,
+            mode: Mode::Browse,
+            message: String::new(),
+            confirm_quit: false,
+        };
+        editor.load(palette);
+        if editor.sections.is_empty() {
+            anyhow::bail!("{} has no colors to edit", path.display());
+        }
+        editor.select(0, 0);
+        Ok(editor)
+    }
+
+    /// Refresh everything derived from `raw`, keeping the selected key.
+    fn reload(&mut self) -> Result<()> {
+        let palette = Palette::parse(&self.raw)?;
+        self.resolved = palette.resolve()?;
+        let selected = self.selected().to_string();
+        self.load(palette);
+        for (s, section) in self.sections.iter().enumerate() {
+            if let Some(r) = section.keys.iter().position(|key| *key == selected) {
+                self.section = s;
+                self.row = r;
+            }
+        }
+        Ok(())
+    }
+
+    fn load(&mut self, palette: Palette) {
+        let generated = palette.generated_keys();
+        self.entries = palette
+            .entries()
+            .into_iter()
+            .filter(|(key, _)| !generated.contains(key))
+            .collect();
+
+        // Sections in palette order, as `show` lists them.
+        self.sections.clear();
+        for (key, _) in palette.entries() {
+            if generated.contains(&key) {
+                continue;
+            }
+            let title = section_title(&key);
+            match self.sections.iter_mut().find(|s| s.title == title) {
+                Some(section) => section.keys.push(key),
+                None => self.sections.push(Section {
+                    title,
+                    keys: vec![key],
+                }),
+            }
+        }
+        self.section = self.section.min(self.sections.len().saturating_sub(1));
+    }
+
+    fn selected(&self) -> &str {
+        self.sections
+            .get(self.section)
+            .and_then(|s| s.keys.get(self.row))
+            .map_or("", String::as_str)
+    }
+
+    fn select(&mut self, section: usize, row: usize) {
+        self.section = section;
+        self.row = row.min(self.sections[section].keys.len() - 1);
+        self.working = None;
+        if let Some(tone) = Tone::of_key(self.selected()) {
+            self.preview = tone;
+        }
+    }
+
+    fn hex(&self, key: &str) -> &str {
+        self.resolved.trace.get(key).map_or("", |t| t.hex.as_str())
+    }
+
+    /// Returns `false` to quit.
+    fn handle(&mut self, key: KeyEvent) -> Result<bool> {
+        let interrupt =
+            key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c');
+        if interrupt {
+            // Quits like `q`, from any mode.
+            self.mode = Mode::Browse;
+        }
+        if let Mode::Reference(input) = &mut self.mode {
+            match key.code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Tab => *input = complete(input, self.resolved.trace.keys()),
+                KeyCode::Enter => {
+                    let value = mem::take(input);
+                    self.mode = Mode::Browse;
+                    self.apply(&value)?;
+                }
+                KeyCode::Esc => self.mode = Mode::Browse,
+                _ => {}
+            }
+            return Ok(true);
+        }
+
+        let quitting = mem::take(&mut self.confirm_quit);
+        self.message.clear();
+        let rows = self.sections[self.section].keys.len();
+        let count = self.sections.len();
+        match key.code {
+            _ if interrupt => return Ok(self.request_quit(quitting)),
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.select(self.section, self.row.saturating_sub(1))
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.section, self.row + 1),
+            KeyCode::Right | KeyCode::Tab => self.select((self.section + 1) % count, 0),
+            KeyCode::Left | KeyCode::BackTab => self.select((self.section + count - 1) % count, 0),
+            KeyCode::Home => self.select(self.section, 0),
+            KeyCode::End => self.select(self.section, rows - 1),
+            KeyCode::Char('l') => self.adjust(-LIGHTNESS_STEP, 0.0, 0.0)?,
+            KeyCode::Char('L') => self.adjust(LIGHTNESS_STEP, 0.0, 0.0)?,
+            KeyCode::Char('c') => self.adjust(0.0, -CHROMA_STEP, 0.0)?,
+            KeyCode::Char('C') => self.adjust(0.0, CHROMA_STEP, 0.0)?,
+            KeyCode::Char('h') => self.adjust(0.0, 0.0, -HUE_STEP)?,
+            KeyCode::Char('H') => self.adjust(0.0, 0.0, HUE_STEP)?,
+            KeyCode::Char('r') => self.mode = Mode::Reference(String::new()),
+            KeyCode::Char('t') => {
+                self.preview = match self.preview {
+                    Tone::Light => Tone::Dark,
+                    Tone::Dark => Tone::Light,
+                }
+            }
+            KeyCode::Char('u') => self.undo()?,
+            KeyCode::Char('s') => self.save()?,
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(self.request_quit(quitting)),
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    /// Returns `false` to quit: right away when there is nothing to lose or
+    /// the previous key already asked, otherwise after asking to confirm.
+    fn request_quit(&mut self, confirmed: bool) -> bool {
+        if confirmed || self.raw == self.saved {
+            return false;
+        }
+        self.confirm_quit = true;
+        self.message = "Unsaved changes: press q again to quit, s to save.".into();
+        true
+    }
+
+    /// Move the selected color through OKLCH, writing it as a literal.
+    fn adjust(&mut self, dl: f32, dc: f32, dh: f32) -> Result<()> {
+        let key = self.selected().to_string();
+        let Some(base) = self.working.or_else(|| Oklch::from_hex(self.hex(&key))) else {
+            return Ok(());
+        };
+        let next = Oklch::new(
+            (base.l + dl).clamp(0.0, 1.0),
+            (base.c + dc).clamp(0.0, MAX_CHROMA),
+            base.h + dh,
+        );
+        if let Some(ColorRef::Path(target)) = self.entries.get(&key) {
+            self.message = format!("{key} no longer references {target}.");
+        }
+        self.apply(&next.to_hex())?;
+        self.working = Some(next);
+        Ok(())
+    }
+
+    /// Set the selected key to a hex color or reference, if the palette
+    /// still resolves.
+    fn apply(&mut self, value: &str) -> Result<()> {
+        let key = self.selected().to_string();
+        match edit::set_color(&self.raw, &key, value) {
+            Ok(raw) if raw == self.raw => {}
+            Ok(raw) => {
+                self.history.push(mem::replace(&mut self.raw, raw));
+                self.working = None;
+                self.reload()?;
+            }
+            Err(err) => self.message = format!("{:#}", anyhow::Error::from(err)),
+        }
+        Ok(())
+    }
+
+    fn undo(&mut self) -> Result<()> {
+        match self.history.pop() {
+            Some(raw) => {
+                self.raw = raw;
+                self.working = None;
+                self.reload()?;
+                self.message = "Undone.".into();
+            }
+            None => self.message = "Nothing to undo.".into(),
+        }
+        Ok(())
+    }
+
+    fn save(&mut self) -> Result<()> {
+        fs::write(&self.path, &self.raw)
+            .with_context(|| format!("writing {}", self.path.display()))?;
+        self.saved = self.raw.clone();
+        self.message = format!("Saved {}.", self.path.display());
+        Ok(())
+    }
+
+    fn draw(&self, out: &mut impl Write) -> Result<()> {
+        let (_, height) = terminal::size()?;
+        let mut lines = Vec::new();
+        let modified = if self.raw == self.saved {
+            ""
+        } else {
+            "  (modified)"
+        };
+        lines.push(format!(
+            "veneer edit · {} · {}{modified}",
+            self.resolved.meta.name,
+            self.path.display()
+        ));
+
+        let section = &self.sections[self.section];
+        lines.push(format!(
+            "◀ {} ▶  {}/{}",
+            section.title,
+            self.section + 1,
+            self.sections.len()
+        ));
+        lines.push(String::new());
+
+        // Key list, scrolled to keep the selection in view.
+        let visible = (height as usize).saturating_sub(CHROME_ROWS).max(3);
+        let start = (self.row + 1).saturating_sub(visible);
+        let width = section
+            .keys
+            .iter()
+            .map(|k| label(section, k).len())
+            .max()
+            .unwrap_or(0);
+        for (r, key) in section.keys.iter().enumerate().skip(start).take(visible) {
+            let marker = if r == self.row { "▸" } else { " " };
+            let hex = self.hex(key);
+            let target = match self.entries.get(key) {
+                Some(ColorRef::Path(path)) => format!("  → {path}"),
+                _ => String::new(),
+            };
+            lines.push(format!(
+                "{marker} {:<width$}  {}  {hex}{target}",
+                label(section, key),
+                swatch(hex)
+            ));
+        }
+        lines.push(String::new());
+
+        let key = self.selected();
+        let hex = self.hex(key);
+        let written = self.entries.get(key).map_or("", ColorRef::as_str);
+        lines.push(format!("{key} = \"{written}\""));
+        if let Some(lch) = self.working.or_else(|| Oklch::from_hex(hex)) {
+            lines.push(format!(
+                "  L {}  {:.3}  l/L",
+                slider(lch.l, |v| Oklch { l: v, ..lch }),
+                lch.l
+            ));
+            lines.push(format!(
+                "  C {}  {:.3}  c/C",
+                slider(lch.c / MAX_CHROMA, |v| Oklch {
+                    c: v * MAX_CHROMA,
+                    ..lch
+                }),
+                lch.c
+            ));
+            lines.push(format!(
+                "  H {}  {:.0}°  h/H",
+                slider(lch.h / 360.0, |v| Oklch {
+                    h: v * 360.0,
+                    ..lch
+                }),
+                lch.h
+            ));
+        }
+        lines.extend(self.contrast_lines(key, hex));
+        lines.push(String::new());
+
+        lines.push(format!("Preview ({})  t: switch tone", self.preview));
+        let colors = SceneColors::new(&self.resolved, self.preview, &BTreeMap::new())?;
+        lines.extend(preview::render(Scene::Terminal, &colors, PREVIEW_WIDTH));
+        lines.push(String::new());
+        lines.extend(preview::render(Scene::Code, &colors, PREVIEW_WIDTH));
+        lines.push(String::new());
+
+        match &self.mode {
+            Mode::Browse => lines.push(
+                "↑↓ key  ←→ section  l c h / L C H adjust  r reference  u undo  s save  q quit"
+                    .into(),
+            ),
+            Mode::Reference(input) => lines.push(format!(
+                "Reference (key or #hex, Tab completes, Enter sets, Esc cancels): {input}█"
+            )),
+        }
+        lines.push(self.message.clone());
+
+        queue!(out, cursor::MoveTo(0, 0), terminal::Clear(ClearType::All))?;
+        for line in lines {
+            queue!(out, Print(line), Print("\r\n"))?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Contrast against the background of the key's tone, or both tones for
+    /// keys that have none (accents, custom groups).
+    fn contrast_lines(&self, key: &str, hex: &str) -> Vec<String> {
+        let tones = match Tone::of_key(key) {
+            Some(tone) => vec![tone],
+            None => Tone::ALL.to_vec(),
+        };
+        tones
+            .into_iter()
+            .filter_map(|tone| {
+                let background = format!("colors.{tone}.background");
+                if background == key {
+                    return None;
+                }
+                let ratio = contrast_ratio(hex, self.hex(&background))?;
+                Some(format!(
+                    "  {ratio:.2}:1 {} on {background}",
+                    wcag_level(ratio)
+                ))
+            })
+            .collect()
+    }
+}
+
+/// `colors.<tone>`, `ansi.<tone>.<level>`, or the top-level table.
+fn section_title(key: &str) -> String {
+    let parts: Vec<&str> = key.split('.').collect();
+    let depth = match parts[0] {
+        "colors" => 2,
+        "ansi" => 3,
+        _ => 1,
+    };
+    parts[..depth.min(parts.len() - 1)].join(".")
+}
+
+fn label<'a>(section: &Section, key: &'a str) -> &'a str {
+    key.strip_prefix(&section.title)
+        .and_then(|rest| rest.strip_prefix('.'))
+        .unwrap_or(key)
+}
+
+/// A bar of the colors along one channel, with `value` (0–1) marked.
+fn slider(value: f32, at: impl Fn(f32) -> Oklch) -> String {
+    let marked = (value.clamp(0.0, 1.0) * (SLIDER_WIDTH - 1) as f32).round() as usize;
+    let mut out = String::new();
+    for i in 0..SLIDER_WIDTH {
+        let v = i as f32 / (SLIDER_WIDTH - 1) as f32;
+        let cell = swatch(&at(v).to_hex());
+        // `swatch` paints six spaces; keep one cell, marked with a bar.
+        let cell = cell.replacen("      ", if i == marked { "┃" } else { " " }, 1);
+        out.push_str(&cell);
+    }
+    out
+}
+
+fn wcag_level(ratio: f32) -> &'static str {
+    match ratio {
+        r if r >= 7.0 => "AAA",
+        r if r >= 4.5 => "AA",
+        r if r >= 3.0 => "AA large",
+        _ => "fail",
+    }
+}
+
+/// Extend `input` to the longest prefix shared by the keys starting with it.
+fn complete<'a>(input: &str, keys: impl Iterator<Item = &'a String>) -> String {
+    let mut matches = keys.filter(|key| key.starts_with(input));
+    let Some(first) = matches.next() else {
+        return input.to_string();
+    };
+    let mut prefix = first.as_str();
+    for key in matches {
+        let shared = prefix
+            .char_indices()
+            .zip(key.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(prefix.len().min(key.len()), |((i, _), _)| i);
+        prefix = &prefix[..shared];
+    }
+    prefix.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TEST_PALETTE;
+
+    fn demo() -> Editor {
+        Editor::new(Path::new("demo.toml"), TEST_PALETTE.to_string()).unwrap()
+    }
+
+    fn press(editor: &mut Editor, code: KeyCode) -> bool {
+        editor.handle(KeyEvent::from(code)).unwrap()
+    }
+
+    fn select(editor: &mut Editor, key: &str) {
+        let (s, r) = editor
+            .sections
+            .iter()
+            .enumerate()
+            .find_map(|(s, section)| Some((s, section.keys.iter().position(|k| k == key)?)))
+            .unwrap();
+        editor.select(s, r);
+    }
+
+    #[test]
+    fn groups_and_labels_keys() {
+        assert_eq!(section_title("colors.dark.background"), "colors.dark");
+        assert_eq!(section_title("colors.dark.diff.added"), "colors.dark");
+        assert_eq!(section_title("ansi.light.bright.red"), "ansi.light.bright");
+        assert_eq!(section_title("accents.info"), "accents");
+        assert_eq!(section_title("ui.sidebar.border"), "ui");
+
+        let section = Section {
+            title: "colors.dark".into(),
+            keys: Vec::new(),
+        };
+        assert_eq!(label(&section, "colors.dark.diff.added"), "diff.added");
+        assert_eq!(label(&section, "accents.info"), "accents.info");
+
+        let editor = demo();
+        let titles: Vec<_> = editor.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            [
+                "colors.light",
+                "colors.dark",
+                "accents",
+                "ansi.light.normal",
+                "ansi.light.bright",
+                "ansi.dark.normal",
+                "ansi.dark.bright",
+            ]
+        );
+        // Scale steps are generated, not edited.
+        assert!(!editor.entries.contains_key("colors.light.blue.100"));
+    }
+
+    #[test]
+    fn rates_contrast() {
+        assert_eq!(wcag_level(21.0), "AAA");
+        assert_eq!(wcag_level(7.0), "AAA");
+        assert_eq!(wcag_level(4.5), "AA");
+        assert_eq!(wcag_level(3.0), "AA large");
+        assert_eq!(wcag_level(2.99), "fail");
+    }
+
+    #[test]
+    fn completes_to_the_shared_prefix() {
+        let keys: Vec<String> = [
+            "colors.dark.background",
+            "colors.dark.border",
+            "colors.light.text",
+        ]
+        .map(String::from)
+        .into();
+        assert_eq!(complete("colors.d", keys.iter()), "colors.dark.b");
+        assert_eq!(
+            complete("colors.dark.ba", keys.iter()),
+            "colors.dark.background"
+        );
+        assert_eq!(complete("colors.", keys.iter()), "colors.");
+        assert_eq!(complete("accents", keys.iter()), "accents");
+    }
+
+    #[test]
+    fn adjusting_a_reference_writes_a_literal_and_undo_restores_it() {
+        let mut editor = demo();
+        select(&mut editor, "colors.dark.primary");
+        assert!(matches!(
+            &editor.entries["colors.dark.primary"],
+            ColorRef::Path(target) if target == "colors.light.primary"
+        ));
+
+        assert!(press(&mut editor, KeyCode::Char('L')));
+        assert_eq!(
+            editor.message,
+            "colors.dark.primary no longer references colors.light.primary."
+        );
+        assert!(matches!(
+            &editor.entries["colors.dark.primary"],
+            ColorRef::Hex(hex) if hex != "#2E73FF"
+        ));
+        assert_eq!(editor.hex("colors.light.primary"), "#2E73FF");
+        assert_eq!(editor.selected(), "colors.dark.primary");
+        // The edit keeps the key's comment.
+        assert!(editor.raw.contains("  # reference to another key"));
+
+        press(&mut editor, KeyCode::Char('u'));
+        assert_eq!(editor.message, "Undone.");
+        assert_eq!(editor.raw, TEST_PALETTE);
+        assert!(matches!(
+            &editor.entries["colors.dark.primary"],
+            ColorRef::Path(target) if target == "colors.light.primary"
+        ));
+        press(&mut editor, KeyCode::Char('u'));
+        assert_eq!(editor.message, "Nothing to undo.");
+    }
+
+    #[test]
+    fn refuses_references_that_make_a_cycle() {
+        let mut editor = demo();
+        select(&mut editor, "colors.light.primary");
+        press(&mut editor, KeyCode::Char('r'));
+        for c in "colors.dark.pri".chars() {
+            press(&mut editor, KeyCode::Char(c));
+        }
+        press(&mut editor, KeyCode::Tab);
+        assert!(matches!(&editor.mode, Mode::Reference(input) if input == "colors.dark.primary"));
+        press(&mut editor, KeyCode::Enter);
+
+        assert!(matches!(editor.mode, Mode::Browse));
+        assert!(editor.message.contains("cycle"), "{}", editor.message);
+        assert_eq!(editor.raw, TEST_PALETTE);
+        assert!(editor.history.is_empty());
+
+        // A good reference is applied.
+        press(&mut editor, KeyCode::Char('r'));
+        for c in "accents.info".chars() {
+            press(&mut editor, KeyCode::Char(c));
+        }
+        press(&mut editor, KeyCode::Enter);
+        assert_eq!(editor.hex("colors.dark.primary"), "#3FA7D6");
+        assert_eq!(editor.history.len(), 1);
+    }
+
+    #[test]
+    fn asks_before_quitting_with_unsaved_changes() {
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+
+        let mut editor = demo();
+        assert!(!editor.handle(ctrl_c).unwrap());
+
+        for quit in [ctrl_c, KeyEvent::from(KeyCode::Char('q'))] {
+            let mut editor = demo();
+            press(&mut editor, KeyCode::Char('l'));
+            assert!(editor.handle(quit).unwrap());
+            assert_eq!(
+                editor.message,
+                "Unsaved changes: press q again to quit, s to save."
+            );
+            assert!(!editor.handle(quit).unwrap());
+        }
+
+        // Another key in between asks again.
+        let mut editor = demo();
+        press(&mut editor, KeyCode::Char('l'));
+        assert!(editor.handle(ctrl_c).unwrap());
+        press(&mut editor, KeyCode::Down);
+        assert!(editor.handle(ctrl_c).unwrap());
+
+        // Ctrl-C leaves reference mode and asks too.
+        press(&mut editor, KeyCode::Char('r'));
+        assert!(editor.handle(ctrl_c).unwrap());
+        assert!(matches!(editor.mode, Mode::Browse));
+        assert!(!editor.handle(ctrl_c).unwrap());
+    }
+}
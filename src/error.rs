@@ -0,0 +1,142 @@
+use std::{io, path::PathBuf};
+
+use crate::palette::Tone;
+
+/// Errors returned by the veneer library.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("reading {}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("writing {}", path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("creating directory {}", path.display())]
+    CreateDir {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("decoding image {}", path.display())]
+    Image {
+        path: PathBuf,
+        #[source]
+        source: image::ImageError,
+    },
+    #[error("no colors to propose a palette from")]
+    NoColors,
+    #[error("reading current directory")]
+    CurrentDir(#[source] io::Error),
+    #[error("parsing JSON")]
+    Json(#[source] serde_json::Error),
+    #[error("invalid design tokens: {0}")]
+    InvalidDtcg(String),
+    #[error("parsing TOML")]
+    Toml(#[source] toml::de::Error),
+    #[error("parsing TOML for editing")]
+    EditToml(#[source] toml_edit::TomlError),
+    #[error("key '{0}' not found in palette file")]
+    KeyNotFound(String),
+    #[error("key '{0}' already exists in palette file")]
+    KeyExists(String),
+    #[error("parsing TOML {}", path.display())]
+    ParseToml {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    #[error("meta.version is not a valid semver version: {version}")]
+    InvalidVersion {
+        version: String,
+        #[source]
+        source: semver::Error,
+    },
+    #[error("tone must be 'light' or 'dark', got '{0}'")]
+    InvalidTone(String),
+    #[error("{label} has invalid hex color: {value}")]
+    InvalidColor { label: String, value: String },
+    #[error("{label} is not a valid scale: {message}")]
+    InvalidScale { label: String, message: String },
+    #[error("invalid hex color: {0}")]
+    InvalidHex(String),
+    #[error("{value} can't anchor a generated palette: {message}")]
+    InvalidAnchor { value: String, message: String },
+    #[error("{label} path must contain at least one '.' segment: {path}")]
+    InvalidPath { label: String, path: String },
+    #[error("{base} has key with '.' in its name: \"{key}\"")]
+    DottedKey { base: String, key: String },
+    #[error(
+        "colors.{tone}.derive must name the other variant, which must not derive itself; got '{value}'"
+    )]
+    InvalidDerive { tone: Tone, value: String },
+    #[error("top-level group name '{0}' is reserved")]
+    ReservedGroup(String),
+
+    #[error(
+        "missing path '{0}'; expected colors.*, accents.*, ansi.*.*.*, tokens.*, or a custom group"
+    )]
+    MissingPath(String),
+    #[error("missing var '{0}'")]
+    MissingVar(String),
+    #[error("cycle detected: {0}")]
+    Cycle(String),
+    #[error("resolving {label} -> {path}")]
+    Resolve {
+        label: String,
+        path: String,
+        #[source]
+        source: Box<Error>,
+    },
+    #[error("resolving {label}")]
+    ResolveVar {
+        label: String,
+        #[source]
+        source: Box<Error>,
+    },
+
+    #[error("unknown format '{0}'")]
+    InvalidFormat(String),
+    #[error("{first} and {second} both map to CSS property --{name}")]
+    CssCollision {
+        name: String,
+        first: String,
+        second: String,
+    },
+    #[error("serializing {format}")]
+    Serialize {
+        format: &'static str,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("no templates matched {}", .0.display())]
+    NoTemplates(PathBuf),
+    #[error("invalid glob pattern")]
+    Pattern(#[from] glob::PatternError),
+    #[error("reading glob match")]
+    Glob(#[from] glob::GlobError),
+    #[error("building template context")]
+    Context(#[source] tera::Error),
+    #[error("registering template {name}")]
+    TemplateParse {
+        name: String,
+        #[source]
+        source: tera::Error,
+    },
+    #[error("rendering template {name}")]
+    TemplateRender {
+        name: String,
+        #[source]
+        source: tera::Error,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
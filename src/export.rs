@@ -0,0 +1,366 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    Error, Result,
+    palette::{ResolvedPalette, Tone},
+};
+
+/// Output formats for a resolved palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Toml,
+    Yaml,
+    /// CSS custom properties on `:root`.
+    Css,
+}
+
+impl Format {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Toml => "toml",
+            Format::Yaml => "yaml",
+            Format::Css => "css",
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(Format::Json),
+            "toml" => Ok(Format::Toml),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "css" => Ok(Format::Css),
+            other => Err(Error::InvalidFormat(other.to_string())),
+        }
+    }
+}
+
+/// Resolved palette as a JSON tree.
+///
+/// With a `tone`, tone-specific sections collapse to that variant: `colors`,
+/// `ansi` and `vars` keep only its table, and each token becomes a single
+/// color. `meta`, `accents` and custom groups are shared by both tones.
+pub fn to_value(resolved: &ResolvedPalette, tone: Option<Tone>) -> Result<Value> {
+    let mut value = serde_json::to_value(resolved).map_err(|source| Error::Serialize {
+        format: "json",
+        source: Box::new(source),
+    })?;
+    strip_nulls(&mut value);
+
+    let Some(tone) = tone else {
+        return Ok(value);
+    };
+    let Value::Object(root) = &mut value else {
+        return Ok(value);
+    };
+    let tone = tone.as_str();
+
+    for section in ["colors", "ansi"] {
+        if let Some(Value::Object(by_tone)) = root.get_mut(section) {
+            let selected = by_tone.remove(tone).unwrap_or_default();
+            root.insert(section.to_string(), selected);
+        }
+    }
+    if let Some(Value::Object(vars)) = root.get_mut("vars") {
+        let selected = vars.remove(tone).unwrap_or_default();
+        root.insert("vars".to_string(), selected);
+    }
+    if let Some(tokens) = root.get_mut("tokens") {
+        select_token_tone(tokens, tone);
+    }
+
+    Ok(value)
+}
+
+/// Flatten a tree into dotted paths (`colors.light.primary`); array items
+/// are keyed by position.
+pub fn flatten(value: &Value) -> BTreeMap<String, Value> {
+    fn walk(value: &Value, prefix: &str, out: &mut BTreeMap<String, Value>) {
+        let join = |key: &str| {
+            if prefix.is_empty() {
+                key.to_string()
+            } else {
+                format!("{prefix}.{key}")
+            }
+        };
+        match value {
+            Value::Object(map) => {
+                for (k, v) in map {
+                    walk(v, &join(k), out);
+                }
+            }
+            Value::Array(items) => {
+                for (i, v) in items.iter().enumerate() {
+                    walk(v, &join(&i.to_string()), out);
+                }
+            }
+            Value::Null => {}
+            leaf => {
+                out.insert(prefix.to_string(), leaf.clone());
+            }
+        }
+    }
+
+    let mut out = BTreeMap::new();
+    walk(value, "", &mut out);
+    out
+}
+
+/// Serialize a resolved palette, optionally restricted to one tone and
+/// flattened to `path = value` pairs.
+pub fn render(
+    resolved: &ResolvedPalette,
+    format: Format,
+    tone: Option<Tone>,
+    flat: bool,
+) -> Result<String> {
+    let tree = to_value(resolved, tone)?;
+    let value = if flat || format == Format::Css {
+        Value::Object(flatten(&tree).into_iter().collect::<Map<_, _>>())
+    } else {
+        tree
+    };
+
+    match format {
+        Format::Json => serde_json::to_string_pretty(&value)
+            .map(|mut s| {
+                s.push('\n');
+                s
+            })
+            .map_err(|source| Error::Serialize {
+                format: "json",
+                source: Box::new(source),
+            }),
+        Format::Toml => toml::to_string(&value).map_err(|source| Error::Serialize {
+            format: "toml",
+            source: Box::new(source),
+        }),
+        Format::Yaml => serde_yaml::to_string(&value).map_err(|source| Error::Serialize {
+            format: "yaml",
+            source: Box::new(source),
+        }),
+        Format::Css => css_properties(&value),
+    }
+}
+
+fn css_properties(flat: &Value) -> Result<String> {
+    let mut out = String::from(":root {\n");
+    let mut names: BTreeMap<String, &str> = BTreeMap::new();
+    if let Value::Object(map) = flat {
+        for (path, value) in map {
+            if path.starts_with("meta.") {
+                continue;
+            }
+            let name = css_name(path);
+            if let Some(first) = names.insert(name.clone(), path) {
+                return Err(Error::CssCollision {
+                    name,
+                    first: first.to_string(),
+                    second: path.clone(),
+                });
+            }
+            let value = match value {
+                Value::String(s) if s.starts_with('#') => s.clone(),
+                Value::String(s) => css_string(s),
+                other => other.to_string(),
+            };
+            out.push_str(&format!("  --{name}: {value};\n"));
+        }
+    }
+    out.push_str("}\n");
+    Ok(out)
+}
+
+/// Custom property name for a dotted path: segments joined with `-`, and
+/// characters CSS identifiers can't hold escaped.
+fn css_name(path: &str) -> String {
+    let mut out = String::new();
+    for c in path.chars() {
+        match c {
+            '.' => out.push('-'),
+            c if c.is_ascii_alphanumeric() || matches!(c, '-' | '_') || !c.is_ascii() => {
+                out.push(c)
+            }
+            c => out.push_str(&format!("\\{:x} ", c as u32)),
+        }
+    }
+    out
+}
+
+/// Double-quoted CSS string; quotes and backslashes are escaped, control
+/// characters written as hex escapes.
+fn css_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_control() => out.push_str(&format!("\\{:x} ", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn select_token_tone(node: &mut Value, tone: &str) {
+    let Value::Object(map) = node else {
+        return;
+    };
+    if map.contains_key("light") && map.contains_key("dark") && map.values().all(Value::is_string) {
+        *node = map.remove(tone).unwrap_or_default();
+        return;
+    }
+    for child in map.values_mut() {
+        select_token_tone(child, tone);
+    }
+}
+
+pub(crate) fn strip_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(strip_nulls);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Palette;
+
+    const PALETTE: &str = r##"
+[meta]
+name = "Test"
+
+[colors.light]
+background = "#FFFFFF"
+
+[colors.dark]
+background = "#000000"
+
+[accents]
+info = "colors.dark.background"
+
+[tokens]
+syntax.comment = { light = "#111111", dark = "#EEEEEE" }
+
+[vars]
+ui_font = "Inter"
+quote = "say \"hi\"\n\\o/"
+
+[ansi.light.normal]
+black="#000000"
+red="#000000"
+green="#000000"
+yellow="#000000"
+blue="#000000"
+magenta="#000000"
+cyan="#000000"
+white="#000000"
+
+[ansi.light.bright]
+black="#111111"
+red="#111111"
+green="#111111"
+yellow="#111111"
+blue="#111111"
+magenta="#111111"
+cyan="#111111"
+white="#111111"
+
+[ansi.dark.normal]
+black="#000000"
+red="#000000"
+green="#000000"
+yellow="#000000"
+blue="#000000"
+magenta="#000000"
+cyan="#000000"
+white="#000000"
+
+[ansi.dark.bright]
+black="#111111"
+red="#111111"
+green="#111111"
+yellow="#111111"
+blue="#111111"
+magenta="#111111"
+cyan="#111111"
+white="#111111"
+"##;
+
+    fn resolved() -> ResolvedPalette {
+        Palette::parse(PALETTE).unwrap().resolve().unwrap()
+    }
+
+    #[test]
+    fn selects_single_tone() {
+        let value = to_value(&resolved(), Some(Tone::Dark)).unwrap();
+        assert_eq!(value["colors"]["background"], "#000000");
+        assert_eq!(value["ansi"]["bright"]["red"], "#111111");
+        assert_eq!(value["tokens"]["syntax"]["comment"], "#EEEEEE");
+        assert_eq!(value["vars"]["ui_font"], "Inter");
+        assert_eq!(value["accents"]["info"], "#000000");
+        assert!(value["meta"].get("version").is_none(), "nulls are dropped");
+    }
+
+    #[test]
+    fn flattens_to_dotted_paths() {
+        let out = render(&resolved(), Format::Toml, Some(Tone::Light), true).unwrap();
+        assert!(out.contains("\"colors.background\" = \"#FFFFFF\""), "{out}");
+        assert!(out.contains("\"ansi.extended.0\" = \"#000000\""), "{out}");
+    }
+
+    #[test]
+    fn renders_css_custom_properties() {
+        let out = render(&resolved(), Format::Css, None, false).unwrap();
+        assert!(out.starts_with(":root {\n"));
+        assert!(
+            out.contains("  --colors-dark-background: #000000;\n"),
+            "{out}"
+        );
+        assert!(
+            out.contains("  --tokens-syntax-comment-light: #111111;\n"),
+            "{out}"
+        );
+        assert!(out.contains("  --vars-ui_font: \"Inter\";\n"), "{out}");
+        assert!(
+            out.contains("  --vars-quote: \"say \\\"hi\\\"\\a \\\\o/\";\n"),
+            "{out}"
+        );
+        assert!(!out.contains("--meta-"), "{out}");
+    }
+
+    #[test]
+    fn reports_css_name_collisions() {
+        let raw = PALETTE.replace(
+            "[accents]\n",
+            "[accents]\n\"side-bar\" = \"#123456\"\nside.bar = \"#654321\"\n",
+        );
+        let resolved = Palette::parse(&raw).unwrap().resolve().unwrap();
+        let err = render(&resolved, Format::Css, None, false).unwrap_err();
+        assert!(
+            matches!(&err, Error::CssCollision { name, .. } if name == "accents-side-bar"),
+            "{err:?}"
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! Dominant colors of an image, for starting a palette from a wallpaper or
+//! brand photo.
+
+use std::path::Path;
+
+use crate::{
+    Error, Result,
+    color::{Oklab, Oklch, contrast_ratio},
+    generate::{self, GenerateOptions, TEXT_CONTRAST},
+};
+
+/// Longest side an image is scaled down to before clustering.
+const SAMPLE_SIZE: u32 = 128;
+/// Upper bound on k-means iterations; most images settle well before.
+const ITERATIONS: usize = 30;
+/// Swatches darker or lighter than this can serve as a background.
+const DARK_BACKGROUND: f32 = 0.3;
+const LIGHT_BACKGROUND: f32 = 0.9;
+
+/// One cluster of image colors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Swatch {
+    pub hex: String,
+    /// Share of the sampled pixels, 0–1.
+    pub weight: f32,
+}
+
+/// Decode an image and return its pixels in OKLab, downscaled and without
+/// transparent pixels.
+pub fn load_pixels(path: &Path) -> Result<Vec<Oklab>> {
+    let image = image::open(path).map_err(|source| Error::Image {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(image
+        .thumbnail(SAMPLE_SIZE, SAMPLE_SIZE)
+        .to_rgba8()
+        .pixels()
+        .filter(|p| p[3] >= 128)
+        .map(|p| Oklab::from_rgb(p[0], p[1], p[2]))
+        .collect())
+}
+
+/// Cluster `pixels` into at most `k` colors with k-means in OKLab, heaviest
+/// first. Seeding is deterministic, so the same image always gives the same
+/// swatches.
+pub fn dominant_colors(pixels: &[Oklab], k: usize) -> Vec<Swatch> {
+    if pixels.is_empty() || k == 0 {
+        return Vec::new();
+    }
+
+    // Farthest-point seeding: start from the pixel farthest from the mean,
+    // then keep adding the pixel farthest from every centroid so far.
+    let mut centroids = vec![mean(pixels.iter())];
+    centroids[0] = farthest(pixels, &centroids).0;
+    while centroids.len() < k {
+        let (far, dist) = farthest(pixels, &centroids);
+        if dist < 1e-4 {
+            break;
+        }
+        centroids.push(far);
+    }
+
+    let mut assignment = vec![usize::MAX; pixels.len()];
+    for _ in 0..ITERATIONS {
+        let mut changed = false;
+        for (slot, &pixel) in assignment.iter_mut().zip(pixels) {
+            let (idx, _) = nearest(&centroids, pixel);
+            changed |= *slot != idx;
+            *slot = idx;
+        }
+        if !changed {
+            break;
+        }
+        for (idx, centroid) in centroids.iter_mut().enumerate() {
+            let members = pixels
+                .iter()
+                .zip(&assignment)
+                .filter(|(_, a)| **a == idx)
+                .map(|(p, _)| p);
+            if let Some(center) = mean_opt(members) {
+                *centroid = center;
+            }
+        }
+    }
+
+    let mut swatches: Vec<Swatch> = centroids
+        .iter()
+        .enumerate()
+        .map(|(idx, centroid)| Swatch {
+            hex: centroid.to_hex(),
+            weight: assignment.iter().filter(|a| **a == idx).count() as f32 / pixels.len() as f32,
+        })
+        .filter(|swatch| swatch.weight > 0.0)
+        .collect();
+    swatches.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+    swatches
+}
+
+fn nearest(centroids: &[Oklab], pixel: Oklab) -> (usize, f32) {
+    centroids
+        .iter()
+        .map(|c| c.distance(pixel))
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .expect("at least one centroid")
+}
+
+/// The pixel farthest from its nearest centroid, and that distance.
+fn farthest(pixels: &[Oklab], centroids: &[Oklab]) -> (Oklab, f32) {
+    pixels
+        .iter()
+        .map(|&p| (p, nearest(centroids, p).1))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .expect("pixels is not empty")
+}
+
+fn mean<'a>(pixels: impl Iterator<Item = &'a Oklab>) -> Oklab {
+    mean_opt(pixels).expect("at least one pixel")
+}
+
+fn mean_opt<'a>(pixels: impl Iterator<Item = &'a Oklab>) -> Option<Oklab> {
+    let (mut l, mut a, mut b, mut n) = (0.0, 0.0, 0.0, 0);
+    for p in pixels {
+        l += p.l;
+        a += p.a;
+        b += p.b;
+        n += 1;
+    }
+    let n = n as f32;
+    (n > 0.0).then(|| Oklab {
+        l: l / n,
+        a: a / n,
+        b: b / n,
+    })
+}
+
+/// Propose a commented `veneer.toml` from image swatches. The heaviest swatch
+/// dark or light enough becomes that tone's background and the swatch with
+/// the most contrast on it (at least 7:1) its text; the most colorful swatch,
+/// favouring large ones, is the seed; and every swatch is a candidate hue for
+/// the ANSI and accent slots. See [`generate::generate`] for the rest.
+pub fn propose(name: &str, source: &str, swatches: &[Swatch]) -> Result<String> {
+    let lch = |swatch: &Swatch| Oklch::from_hex(&swatch.hex).expect("swatches are hex");
+    let Some(seed) = swatches.iter().max_by(|a, b| {
+        let score = |s: &Swatch| lch(s).c * s.weight.sqrt();
+        score(a).total_cmp(&score(b))
+    }) else {
+        return Err(Error::NoColors);
+    };
+
+    let background = swatches.iter().find(|swatch| {
+        let l = lch(swatch).l;
+        !(DARK_BACKGROUND..=LIGHT_BACKGROUND).contains(&l)
+    });
+    let foreground = background.and_then(|bg| {
+        swatches
+            .iter()
+            .map(|s| (s, contrast_ratio(&s.hex, &bg.hex).unwrap_or(1.0)))
+            .filter(|(_, ratio)| *ratio >= TEXT_CONTRAST)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(s, _)| s)
+    });
+
+    let generated = generate::generate(&GenerateOptions {
+        name: name.to_string(),
+        seed: seed.hex.clone(),
+        background: background.map(|s| s.hex.clone()),
+        foreground: foreground.map(|s| s.hex.clone()),
+        swatches: swatches.iter().map(|s| s.hex.clone()).collect(),
+    })?;
+
+    let colors: Vec<String> = swatches
+        .iter()
+        .map(|s| format!("{} {:.0}%", s.hex, s.weight * 100.0))
+        .collect();
+    Ok(format!(
+        "# Proposed from {source} by `veneer extract`.\n# Image colors: {}.\n{generated}",
+        colors.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Palette;
+
+    fn pixels(colors: &[(&str, usize)]) -> Vec<Oklab> {
+        colors
+            .iter()
+            .flat_map(|(hex, n)| std::iter::repeat_n(Oklab::from_hex(hex).unwrap(), *n))
+            .collect()
+    }
+
+    #[test]
+    fn clusters_dominant_colors() {
+        let mut image = pixels(&[("#101418", 60), ("#E6A700", 30), ("#F0F0F0", 10)]);
+        // Slight noise around the dark color still lands in its cluster.
+        image.extend(pixels(&[("#12161A", 5), ("#0E1216", 5)]));
+
+        let swatches = dominant_colors(&image, 3);
+        let found: Vec<_> = swatches.iter().map(|s| s.hex.as_str()).collect();
+        assert_eq!(found, ["#101418", "#E6A700", "#F0F0F0"]);
+        assert!((swatches[0].weight - 70.0 / 110.0).abs() < 1e-4);
+
+        // Fewer distinct colors than requested.
+        assert_eq!(dominant_colors(&pixels(&[("#336699", 4)]), 8).len(), 1);
+        assert!(dominant_colors(&[], 8).is_empty());
+    }
+
+    #[test]
+    fn proposes_palette_from_swatches() {
+        let swatches = dominant_colors(
+            &pixels(&[("#101418", 60), ("#E6A700", 30), ("#F0F0F0", 10)]),
+            3,
+        );
+        let toml = propose("Wallpaper", "wall.png", &swatches).unwrap();
+        assert!(toml.starts_with("# Proposed from wall.png by `veneer extract`.\n"));
+
+        let palette = Palette::parse(&toml).unwrap();
+        assert_eq!(palette.meta.name, "Wallpaper");
+        let resolved = palette.resolve().unwrap();
+        let get = |key: &str| resolved.trace[key].hex.clone();
+        assert_eq!(get("colors.dark.background"), "#101418");
+        assert_eq!(get("colors.dark.text"), "#F0F0F0");
+        // The amber swatch is the seed, and the yellow slot snaps to its hue.
+        let amber = Oklch::from_hex("#E6A700").unwrap().h;
+        let yellow = Oklch::from_hex(&get("ansi.light.normal.yellow")).unwrap().h;
+        assert!((amber - yellow).abs() < 2.0, "{amber} vs {yellow}");
+    }
+}
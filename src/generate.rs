@@ -0,0 +1,552 @@
+//! Generate a starter palette from a seed color.
+//!
+//! Everything is derived in OKLCH: neutrals share the seed's hue at low
+//! chroma, ANSI hues sit at their usual angles nudged toward the seed, and
+//! lightness is walked away from each background until the color reaches its
+//! WCAG contrast target.
+
+use std::fmt::Write as _;
+
+use crate::{
+    Error, Palette, Result,
+    color::{Lab, Oklch, contrast_ratio, delta_e},
+    lint::NEAR_DUPLICATE_THRESHOLD,
+    palette::{ANSI_NAMES, Tone},
+};
+
+/// Contrast body text reaches on its background.
+pub(crate) const TEXT_CONTRAST: f32 = 7.0;
+/// Contrast muted text, primary and normal ANSI colors reach.
+const UI_CONTRAST: f32 = 4.5;
+
+/// OKLCH hues of the six chromatic ANSI colors, red through cyan in ANSI order.
+const ANSI_HUES: [(&str, f32); 6] = [
+    ("red", 29.0),
+    ("green", 142.0),
+    ("yellow", 100.0),
+    ("blue", 264.0),
+    ("magenta", 328.0),
+    ("cyan", 195.0),
+];
+
+/// How far ANSI hues lean toward the seed hue, and the cap in degrees.
+const HUE_PULL: f32 = 0.25;
+const MAX_HUE_SHIFT: f32 = 15.0;
+/// A swatch replaces a slot's hue when this close to it, in degrees...
+const SWATCH_RANGE: f32 = 20.0;
+/// ...unless that would bring two slots closer than this.
+const MIN_HUE_GAP: f32 = 35.0;
+/// Swatches below this chroma have no meaningful hue.
+const MIN_SWATCH_CHROMA: f32 = 0.04;
+
+/// Inputs for [`generate`].
+#[derive(Debug, Clone)]
+pub struct GenerateOptions {
+    /// Written to `meta.name`.
+    pub name: String,
+    /// Seed color; becomes `primary` and tints the neutrals.
+    pub seed: String,
+    /// Background to keep as-is; a dark color anchors the dark tone, a light
+    /// one the light tone.
+    pub background: Option<String>,
+    /// Text color to keep as-is, for the same tone as `background` (or, without
+    /// one, the tone it reads on).
+    pub foreground: Option<String>,
+    /// Colors to take ANSI and accent hues from: each slot uses the hue of a
+    /// nearby chromatic swatch when there is one.
+    pub swatches: Vec<String>,
+}
+
+/// Generate a commented `veneer.toml`. The result always parses as a valid
+/// [`Palette`]; anchors that keep text or UI colors from reaching their
+/// contrast targets are rejected with [`Error::InvalidAnchor`].
+pub fn generate(options: &GenerateOptions) -> Result<String> {
+    let seed =
+        Oklch::from_hex(&options.seed).ok_or_else(|| Error::InvalidHex(options.seed.clone()))?;
+    let anchor = |hex: &Option<String>| -> Result<Option<Oklch>> {
+        hex.as_ref()
+            .map(|hex| Oklch::from_hex(hex).ok_or_else(|| Error::InvalidHex(hex.clone())))
+            .transpose()
+    };
+    let swatches = options
+        .swatches
+        .iter()
+        .map(|hex| Oklch::from_hex(hex).ok_or_else(|| Error::InvalidHex(hex.clone())))
+        .collect::<Result<Vec<_>>>()?;
+    let hues = HuePicker {
+        seed: seed.h,
+        swatches,
+    };
+    let background = anchor(&options.background)?;
+    let foreground = anchor(&options.foreground)?;
+    let anchored_tone = match (background, foreground) {
+        (Some(bg), _) => Some(tone_of(bg)),
+        // Light text belongs on a dark background.
+        (None, Some(fg)) if fg.l >= 0.5 => Some(Tone::Dark),
+        (None, Some(_)) => Some(Tone::Light),
+        (None, None) => None,
+    };
+
+    let mut out = Writer::default();
+    writeln!(
+        out.text,
+        "# Generated from seed {}.\n\
+         # Neutrals share the seed's hue; text reaches {TEXT_CONTRAST}:1 contrast on its\n\
+         # background, and muted text, primary and normal ANSI colors {UI_CONTRAST}:1.\n\
+         # Trailing comments give each color's contrast on its tone's background.",
+        options.seed.to_uppercase()
+    )
+    .unwrap();
+
+    out.section("meta", None);
+    out.string("name", &options.name);
+    out.string("version", "0.1.0");
+
+    let mut tones = Vec::new();
+    for tone in Tone::ALL {
+        let anchors = if anchored_tone == Some(tone) {
+            (background, foreground)
+        } else {
+            (None, None)
+        };
+        tones.push((tone, ToneColors::new(tone, seed, &hues, anchors)?));
+    }
+
+    for (tone, colors) in &tones {
+        out.section(
+            &format!("colors.{tone}"),
+            Some(match tone {
+                Tone::Light => "Light tone: neutrals step down from the background.",
+                Tone::Dark => "Dark tone: neutrals step up from the background.",
+            }),
+        );
+        let bg = colors.background.clone();
+        out.color("background", &bg, None);
+        out.color("surface", &colors.surface, None);
+        out.color("border", &colors.border, Some(&bg));
+        out.color("text", &colors.text, Some(&bg));
+        out.color("muted", &colors.muted, Some(&bg));
+        out.color("primary", &colors.primary, Some(&bg));
+    }
+
+    out.section(
+        "accents",
+        Some("Shared by both tones, at a lightness that reads on either."),
+    );
+    let accents = ["info", "success", "warning", "error"];
+    for (name, hue) in accents.iter().zip(hues.pick(&[264.0, 142.0, 70.0, 29.0])) {
+        let color = Oklch::new(0.68, 0.14, hue).to_hex();
+        out.color(name, &color, None);
+    }
+
+    for (tone, colors) in &tones {
+        let bg = colors.background.clone();
+        for (level, row) in [
+            ("normal", &colors.ansi_normal),
+            ("bright", &colors.ansi_bright),
+        ] {
+            out.section(&format!("ansi.{tone}.{level}"), None);
+            for (name, hex) in ANSI_NAMES.iter().zip(row) {
+                match (level, *name) {
+                    // As in the example palette, black is the background side
+                    // and white the foreground in both tones.
+                    ("normal", "black") => {
+                        out.reference(name, &format!("colors.{tone}.background"))
+                    }
+                    ("normal", "white") => out.reference(name, &format!("colors.{tone}.text")),
+                    _ => out.color(name, hex, Some(&bg)),
+                }
+            }
+        }
+    }
+
+    let text = out.text;
+    Palette::parse(&text)?;
+    Ok(text)
+}
+
+fn tone_of(color: Oklch) -> Tone {
+    if color.l < 0.5 {
+        Tone::Dark
+    } else {
+        Tone::Light
+    }
+}
+
+/// Lean `hue` toward `seed`, by [`HUE_PULL`] of the gap and at most
+/// [`MAX_HUE_SHIFT`] degrees.
+fn harmonize(hue: f32, seed: f32) -> f32 {
+    let gap = (seed - hue + 540.0).rem_euclid(360.0) - 180.0;
+    hue + (gap * HUE_PULL).clamp(-MAX_HUE_SHIFT, MAX_HUE_SHIFT)
+}
+
+/// Chooses hues for the ANSI and accent slots.
+struct HuePicker {
+    seed: f32,
+    swatches: Vec<Oklch>,
+}
+
+impl HuePicker {
+    /// One hue per base hue: the nearest chromatic swatch within
+    /// [`SWATCH_RANGE`], or the base harmonized toward the seed.
+    fn pick(&self, bases: &[f32]) -> Vec<f32> {
+        let mut picked: Vec<f32> = Vec::new();
+        for &base in bases {
+            let swatch = self
+                .swatches
+                .iter()
+                .filter(|s| s.c >= MIN_SWATCH_CHROMA && hue_gap(s.h, base) <= SWATCH_RANGE)
+                .filter(|s| picked.iter().all(|&p| hue_gap(s.h, p) >= MIN_HUE_GAP))
+                .min_by(|a, b| hue_gap(a.h, base).total_cmp(&hue_gap(b.h, base)));
+            picked.push(swatch.map_or_else(|| harmonize(base, self.seed), |s| s.h));
+        }
+        picked
+    }
+}
+
+/// Angle between two hues, 0–180 degrees.
+fn hue_gap(a: f32, b: f32) -> f32 {
+    let gap = (a - b).rem_euclid(360.0);
+    gap.min(360.0 - gap)
+}
+
+/// Walk `color`'s lightness away from `background` until it reaches
+/// `target` contrast (or runs out of range).
+fn fit_contrast(mut color: Oklch, background: &str, target: f32) -> String {
+    let step = match Oklch::from_hex(background).map(tone_of) {
+        Some(Tone::Dark) => 0.005,
+        _ => -0.005,
+    };
+    loop {
+        let hex = color.to_hex();
+        let next = color.l + step;
+        if contrast_ratio(&hex, background).unwrap_or(21.0) >= target
+            || !(0.0..=1.0).contains(&next)
+        {
+            return hex;
+        }
+        color.l = next;
+    }
+}
+
+/// Literal colors for one tone.
+struct ToneColors {
+    background: String,
+    surface: String,
+    border: String,
+    text: String,
+    muted: String,
+    primary: String,
+    ansi_normal: [String; 8],
+    ansi_bright: [String; 8],
+}
+
+impl ToneColors {
+    /// Fails when an anchor keeps text, muted text, primary or the normal
+    /// ANSI colors from reaching their contrast targets.
+    fn new(
+        tone: Tone,
+        seed: Oklch,
+        hues: &HuePicker,
+        (background, foreground): (Option<Oklch>, Option<Oklch>),
+    ) -> Result<Self> {
+        let neutral = (seed.c * 0.12).min(0.015);
+        let dark = tone == Tone::Dark;
+        // Lightness moves away from the background in this direction.
+        let away = if dark { 1.0 } else { -1.0 };
+
+        let bg = background.unwrap_or(if dark {
+            Oklch::new(0.18, neutral, seed.h)
+        } else {
+            Oklch::new(0.97, neutral * 0.5, seed.h)
+        });
+        let background = bg.to_hex();
+        let shade = |delta: f32| Oklch::new(bg.l + away * delta, bg.c, bg.h);
+        let check = |hex: &str, target: f32, what: &str| -> Result<()> {
+            let ratio = contrast_ratio(hex, &background).unwrap_or(1.0);
+            if ratio >= target {
+                return Ok(());
+            }
+            let (value, message) = match foreground {
+                Some(_) if what == "text" => (
+                    hex.to_string(),
+                    format!(
+                        "text has contrast {ratio:.2}:1 on background {background}; needs {target}:1"
+                    ),
+                ),
+                _ => (
+                    background.clone(),
+                    format!("{what} can't reach {target}:1 contrast on it"),
+                ),
+            };
+            Err(Error::InvalidAnchor { value, message })
+        };
+
+        let text = match foreground {
+            Some(fg) => fg.to_hex(),
+            None => fit_contrast(
+                Oklch::new(if dark { 0.85 } else { 0.35 }, 0.01, seed.h),
+                &background,
+                TEXT_CONTRAST,
+            ),
+        };
+        check(&text, TEXT_CONTRAST, "text")?;
+        let text_l = Oklch::from_hex(&text).map_or(0.5, |c| c.l);
+        let muted = fit_contrast(
+            Oklch::new((bg.l + text_l) / 2.0, neutral, seed.h),
+            &background,
+            UI_CONTRAST,
+        );
+        check(&muted, UI_CONTRAST, "muted text")?;
+        let primary = fit_contrast(seed, &background, UI_CONTRAST);
+        check(&primary, UI_CONTRAST, "primary")?;
+        let mut taken = vec![background.clone(), text.clone(), muted.clone()];
+        // Near black or white, small lightness steps vanish in sRGB.
+        let surface = distinct(shade(0.035), away, &mut taken);
+        let border = distinct(shade(0.12), away, &mut taken);
+        // A gray seed would otherwise land on muted text.
+        let primary = distinct(Oklch::from_hex(&primary).unwrap(), away, &mut taken);
+
+        let chroma = seed.c.clamp(0.09, 0.16);
+        let start = if dark { 0.62 } else { 0.58 };
+        let mut normal: [String; 8] = Default::default();
+        let mut bright: [String; 8] = Default::default();
+        normal[0] = background.clone();
+        normal[7] = text.clone();
+        bright[0] = distinct(shade(0.22), away, &mut taken);
+        let bases = ANSI_HUES.map(|(_, hue)| hue);
+        for ((name, _), hue) in ANSI_HUES.iter().zip(hues.pick(&bases)) {
+            let idx = ANSI_NAMES.iter().position(|n| n == name).unwrap();
+            let fitted = fit_contrast(Oklch::new(start, chroma, hue), &background, UI_CONTRAST);
+            check(&fitted, UI_CONTRAST, &format!("ansi {name}"))?;
+            // Moving away from the background only adds contrast.
+            normal[idx] = distinct(Oklch::from_hex(&fitted).unwrap(), away, &mut taken);
+            // Bright colors are lighter in both tones.
+            let l = Oklch::from_hex(&normal[idx]).map_or(start, |c| c.l);
+            bright[idx] = distinct(
+                Oklch::new((l + 0.08).min(0.97), chroma, hue),
+                1.0,
+                &mut taken,
+            );
+        }
+        let bright_white = if dark {
+            Oklch::new((text_l + 0.05).min(0.99), 0.005, seed.h)
+        } else {
+            Oklch::new(text_l + 0.12, 0.01, seed.h)
+        };
+        bright[7] = distinct(bright_white, away, &mut taken);
+        let lightness = |hex: &str| Lab::from_hex(hex).map_or(0.0, |lab| lab.l);
+        if lightness(&bright[7]) <= lightness(&text) {
+            return Err(Error::InvalidAnchor {
+                value: text,
+                message: "bright white can't be lighter than it".into(),
+            });
+        }
+
+        Ok(Self {
+            surface,
+            border,
+            background,
+            text,
+            muted,
+            primary,
+            ansi_normal: normal,
+            ansi_bright: bright,
+        })
+    }
+}
+
+/// Walk `color`'s lightness in `direction` until it is at least
+/// [`NEAR_DUPLICATE_THRESHOLD`] from every color in `taken`, then add it to
+/// `taken`. Keeps one tone's literals from reading as near-duplicates. When
+/// lightness runs out of range, the other direction is tried.
+fn distinct(color: Oklch, direction: f32, taken: &mut Vec<String>) -> String {
+    let clear = |hex: &str| {
+        taken
+            .iter()
+            .all(|seen| delta_e(seen, hex).is_none_or(|de| de >= NEAR_DUPLICATE_THRESHOLD))
+    };
+    let walk = |direction: f32| {
+        let mut color = color;
+        loop {
+            let hex = color.to_hex();
+            if clear(&hex) {
+                return Some(hex);
+            }
+            color.l += 0.005 * direction;
+            if !(0.0..=1.0).contains(&color.l) {
+                return None;
+            }
+        }
+    };
+    let hex = walk(direction)
+        .or_else(|| walk(-direction))
+        .unwrap_or_else(|| color.to_hex());
+    taken.push(hex.clone());
+    hex
+}
+
+/// Builds the TOML text.
+#[derive(Default)]
+struct Writer {
+    text: String,
+    section: String,
+}
+
+impl Writer {
+    fn section(&mut self, name: &str, comment: Option<&str>) {
+        self.text.push('\n');
+        if let Some(comment) = comment {
+            writeln!(self.text, "# {comment}").unwrap();
+        }
+        writeln!(self.text, "[{name}]").unwrap();
+        self.section = name.to_string();
+    }
+
+    fn string(&mut self, key: &str, value: &str) {
+        writeln!(self.text, "{key:<10} = {value:?}").unwrap();
+    }
+
+    fn reference(&mut self, key: &str, path: &str) {
+        writeln!(self.text, "{key:<10} = \"{path}\"").unwrap();
+    }
+
+    /// A color, with its contrast on `background` as a comment when given.
+    fn color(&mut self, key: &str, hex: &str, background: Option<&str>) {
+        let value = format!("\"{hex}\"");
+        match background.and_then(|bg| contrast_ratio(hex, bg)) {
+            Some(ratio) => writeln!(self.text, "{key:<10} = {value:<26} # {ratio:.1}:1").unwrap(),
+            None => writeln!(self.text, "{key:<10} = {value}").unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint;
+
+    fn options(seed: &str) -> GenerateOptions {
+        GenerateOptions {
+            name: "Generated".into(),
+            seed: seed.into(),
+            background: None,
+            foreground: None,
+            swatches: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn generated_palettes_pass_lints() {
+        let anchored = |background: Option<&str>, foreground: Option<&str>| GenerateOptions {
+            background: background.map(Into::into),
+            foreground: foreground.map(Into::into),
+            ..options("#2E73FF")
+        };
+        let mut cases: Vec<GenerateOptions> =
+            ["#2E73FF", "#E6A700", "#CC241D", "#8EC07C", "#777777"]
+                .into_iter()
+                .map(options)
+                .collect();
+        cases.extend([
+            anchored(Some("#FFFFFF"), None),
+            anchored(None, Some("#F0F0F0")),
+            anchored(Some("#FFFFFF"), Some("#111111")),
+            anchored(Some("#000000"), None),
+            anchored(Some("#101418"), Some("#F0F0F0")),
+        ]);
+
+        for case in &cases {
+            let seed = format!("{} {:?} {:?}", case.seed, case.background, case.foreground);
+            let toml = generate(case).unwrap();
+            let palette = Palette::parse(&toml).unwrap();
+            let resolved = palette.resolve().unwrap();
+
+            let mut lints = lint::duplicate_colors(&palette);
+            lints.extend(lint::near_duplicates(&palette, NEAR_DUPLICATE_THRESHOLD));
+            lints.extend(lint::ansi_schemes(&resolved, lint::ANSI_DISTINCT_THRESHOLD));
+            assert!(lints.is_empty(), "{seed}: {lints:#?}\n{toml}");
+
+            for (tone, ansi) in [
+                (&resolved.colors.light, &resolved.ansi.light),
+                (&resolved.colors.dark, &resolved.ansi.dark),
+            ] {
+                let hex = |key: &str| match &tone[key] {
+                    crate::palette::ResolvedNode::Color(hex) => hex.clone(),
+                    other => panic!("{other:?}"),
+                };
+                let ratio = contrast_ratio(&hex("text"), &hex("background")).unwrap();
+                assert!(ratio >= TEXT_CONTRAST, "{seed}: text {ratio}");
+                for key in ["muted", "primary"] {
+                    let ratio = contrast_ratio(&hex(key), &hex("background")).unwrap();
+                    assert!(ratio >= UI_CONTRAST, "{seed}: {key} {ratio}");
+                }
+                for color in &ansi.normal.colors()[1..7] {
+                    let ratio = contrast_ratio(color, &hex("background")).unwrap();
+                    assert!(ratio >= UI_CONTRAST, "{seed}: ansi {color} {ratio}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_anchors_and_comments() {
+        let toml = generate(&GenerateOptions {
+            background: Some("#101418".into()),
+            foreground: Some("#F0F0F0".into()),
+            ..options("#2E73FF")
+        })
+        .unwrap();
+        assert!(toml.starts_with("# Generated from seed #2E73FF.\n"));
+        assert!(
+            toml.contains("[colors.dark]\nbackground = \"#101418\"\n"),
+            "{toml}"
+        );
+        assert!(toml.contains("text       = \"#F0F0F0\""), "{toml}");
+        assert!(toml.contains(":1\n"), "contrast comments: {toml}");
+
+        let err = generate(&options("blue")).unwrap_err();
+        assert!(matches!(err, Error::InvalidHex(s) if s == "blue"));
+        // Seven bytes, but not seven hex digits.
+        let err = generate(&options("#aébcd")).unwrap_err();
+        assert!(matches!(err, Error::InvalidHex(s) if s == "#aébcd"));
+        let err = generate(&GenerateOptions {
+            background: Some("#aébcd".into()),
+            ..options("#2E73FF")
+        })
+        .unwrap_err();
+        assert!(matches!(err, Error::InvalidHex(s) if s == "#aébcd"));
+    }
+
+    #[test]
+    fn rejects_anchors_that_miss_contrast_targets() {
+        let anchored = |background: Option<&str>, foreground: Option<&str>| {
+            generate(&GenerateOptions {
+                background: background.map(Into::into),
+                foreground: foreground.map(Into::into),
+                ..options("#2E73FF")
+            })
+            .unwrap_err()
+            .to_string()
+        };
+
+        assert_eq!(
+            anchored(Some("#808080"), None),
+            "#808080 can't anchor a generated palette: text can't reach 7:1 contrast on it"
+        );
+        assert_eq!(
+            anchored(Some("#FFFFFF"), Some("#FFFFFF")),
+            "#FFFFFF can't anchor a generated palette: \
+             text has contrast 1.00:1 on background #FFFFFF; needs 7:1"
+        );
+        assert_eq!(
+            anchored(Some("#FFFFFF"), Some("#EEEEEE")),
+            "#EEEEEE can't anchor a generated palette: \
+             text has contrast 1.16:1 on background #FFFFFF; needs 7:1"
+        );
+        assert_eq!(
+            anchored(None, Some("#FFFFFF")),
+            "#FFFFFF can't anchor a generated palette: bright white can't be lighter than it"
+        );
+    }
+}
@@ -0,0 +1,439 @@
+//! Reference graph between palette keys.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    fmt::Write as _,
+    str::FromStr,
+};
+
+use crate::{
+    Error, Result,
+    color::Oklab,
+    palette::{ColorRef, Palette, Trace},
+};
+
+/// Diagram formats for [`RefGraph::render`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz DOT.
+    Dot,
+    Mermaid,
+}
+
+impl GraphFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "dot",
+            GraphFormat::Mermaid => "mermaid",
+        }
+    }
+}
+
+impl fmt::Display for GraphFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "dot" | "graphviz" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            other => Err(Error::InvalidFormat(other.to_string())),
+        }
+    }
+}
+
+/// Every color key and the value it was declared with; references are the
+/// edges `resolve` follows.
+#[derive(Debug, Clone)]
+pub struct RefGraph {
+    refs: BTreeMap<String, ColorRef>,
+}
+
+impl RefGraph {
+    pub fn new(palette: &Palette) -> Self {
+        Self {
+            refs: palette.entries().into_iter().collect(),
+        }
+    }
+
+    /// The graph of a resolved palette: each key points at the first path
+    /// its chain followed, or at its hex when it was a literal.
+    pub fn from_trace(trace: &BTreeMap<String, Trace>) -> Self {
+        Self {
+            refs: trace
+                .iter()
+                .map(|(key, trace)| {
+                    let cref = match trace.chain.first() {
+                        Some(path) => ColorRef::Path(path.clone()),
+                        None => ColorRef::Hex(trace.hex.clone()),
+                    };
+                    (key.clone(), cref)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.refs.contains_key(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&ColorRef> {
+        self.refs.get(key)
+    }
+
+    /// `(key, value)` pairs sorted by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ColorRef)> {
+        self.refs.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Keys that reference `key` directly.
+    pub fn direct_dependents(&self, key: &str) -> Vec<&str> {
+        self.iter()
+            .filter(|(_, cref)| matches!(cref, ColorRef::Path(p) if p == key))
+            .map(|(k, _)| k)
+            .collect()
+    }
+
+    /// Keys that reference `key` directly or through other references.
+    pub fn dependents(&self, key: &str) -> BTreeSet<&str> {
+        let mut seen = BTreeSet::new();
+        let mut queue = vec![key];
+        while let Some(next) = queue.pop() {
+            for dependent in self.direct_dependents(next) {
+                if dependent != key && seen.insert(dependent) {
+                    queue.push(dependent);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Hex a key resolves to, or `None` when its chain hits a cycle or a
+    /// missing path. Unlike [`Palette::resolve`] this never fails.
+    pub fn hex(&self, key: &str) -> Option<String> {
+        let mut seen = BTreeSet::new();
+        let mut current = key;
+        loop {
+            if !seen.insert(current) {
+                return None;
+            }
+            match self.refs.get(current)? {
+                ColorRef::Hex(hex) => return Some(hex.to_uppercase()),
+                ColorRef::Path(next) => current = next,
+            }
+        }
+    }
+
+    /// Keys that are part of a reference cycle.
+    pub fn cycles(&self) -> BTreeSet<&str> {
+        let mut out = BTreeSet::new();
+        for start in self.refs.keys() {
+            let mut chain = vec![start.as_str()];
+            while let Some(ColorRef::Path(next)) = self.refs.get(*chain.last().unwrap()) {
+                if let Some(pos) = chain.iter().position(|k| k == next) {
+                    out.extend(&chain[pos..]);
+                    break;
+                }
+                if !self.refs.contains_key(next) {
+                    break;
+                }
+                chain.push(next);
+            }
+        }
+        out
+    }
+
+    /// Render the graph as a diagram. Nodes are filled with their resolved
+    /// color; literals are boxes and aliases rounded, edges point from an
+    /// alias to its target, and cycles are drawn in red.
+    pub fn render(&self, format: GraphFormat) -> String {
+        let nodes = self.nodes();
+        let cycles = self.cycles();
+        match format {
+            GraphFormat::Dot => self.to_dot(&nodes, &cycles),
+            GraphFormat::Mermaid => self.to_mermaid(&nodes, &cycles),
+        }
+    }
+
+    /// Every key plus referenced paths that don't exist, with their hex.
+    fn nodes(&self) -> Vec<Node<'_>> {
+        let mut nodes: Vec<Node> = self
+            .iter()
+            .map(|(key, cref)| Node {
+                key,
+                kind: match cref {
+                    ColorRef::Hex(_) => NodeKind::Literal,
+                    ColorRef::Path(_) => NodeKind::Alias,
+                },
+                hex: self.hex(key),
+            })
+            .collect();
+        let missing: BTreeSet<&str> = self
+            .refs
+            .values()
+            .filter_map(|cref| match cref {
+                ColorRef::Path(p) if !self.refs.contains_key(p) => Some(p.as_str()),
+                _ => None,
+            })
+            .collect();
+        nodes.extend(missing.into_iter().map(|key| Node {
+            key,
+            kind: NodeKind::Missing,
+            hex: None,
+        }));
+        nodes
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.iter().filter_map(|(key, cref)| match cref {
+            ColorRef::Path(target) => Some((key, target.as_str())),
+            ColorRef::Hex(_) => None,
+        })
+    }
+
+    fn to_dot(&self, nodes: &[Node], cycles: &BTreeSet<&str>) -> String {
+        let mut out = String::from("digraph palette {\n");
+        out.push_str("  rankdir=LR;\n");
+        out.push_str("  node [fontname=\"monospace\"];\n");
+        for node in nodes {
+            let style = match node.kind {
+                NodeKind::Literal => "filled",
+                NodeKind::Alias => "rounded,filled",
+                NodeKind::Missing => "rounded,dashed",
+            };
+            let mut attrs = format!(
+                "shape=box, style=\"{style}\", label=\"{}\"",
+                node.label("\\n")
+            );
+            match &node.hex {
+                Some(hex) => {
+                    let _ = write!(
+                        attrs,
+                        ", fillcolor=\"{hex}\", fontcolor=\"{}\"",
+                        text_color(hex)
+                    );
+                }
+                None => attrs.push_str(", fillcolor=\"#FFFFFF\""),
+            }
+            if cycles.contains(node.key) {
+                attrs.push_str(", color=\"red\", penwidth=2");
+            }
+            let _ = writeln!(out, "  \"{}\" [{attrs}];", node.key);
+        }
+        for (from, to) in self.edges() {
+            let attrs = if cycles.contains(from) && cycles.contains(to) {
+                " [color=\"red\", penwidth=2]"
+            } else {
+                ""
+            };
+            let _ = writeln!(out, "  \"{from}\" -> \"{to}\"{attrs};");
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn to_mermaid(&self, nodes: &[Node], cycles: &BTreeSet<&str>) -> String {
+        let ids: BTreeMap<&str, String> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.key, format!("n{i}")))
+            .collect();
+
+        let mut out = String::from("graph LR\n");
+        for node in nodes {
+            let label = node.label("<br/>");
+            let id = &ids[node.key];
+            let _ = match node.kind {
+                NodeKind::Literal => writeln!(out, "  {id}[\"{label}\"]"),
+                NodeKind::Alias | NodeKind::Missing => writeln!(out, "  {id}(\"{label}\")"),
+            };
+        }
+        let mut cycle_links = Vec::new();
+        for (i, (from, to)) in self.edges().enumerate() {
+            let _ = writeln!(out, "  {} --> {}", ids[from], ids[to]);
+            if cycles.contains(from) && cycles.contains(to) {
+                cycle_links.push(i.to_string());
+            }
+        }
+        for node in nodes {
+            let mut style = match &node.hex {
+                Some(hex) => format!("fill:{hex},color:{}", text_color(hex)),
+                None => "fill:#FFFFFF".to_string(),
+            };
+            if node.kind == NodeKind::Missing {
+                style.push_str(",stroke-dasharray:4 4");
+            }
+            if cycles.contains(node.key) {
+                style.push_str(",stroke:red,stroke-width:2px");
+            }
+            let _ = writeln!(out, "  style {} {style}", ids[node.key]);
+        }
+        if !cycle_links.is_empty() {
+            let _ = writeln!(
+                out,
+                "  linkStyle {} stroke:red,stroke-width:2px",
+                cycle_links.join(",")
+            );
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NodeKind {
+    Literal,
+    Alias,
+    /// Referenced but not defined.
+    Missing,
+}
+
+struct Node<'a> {
+    key: &'a str,
+    kind: NodeKind,
+    hex: Option<String>,
+}
+
+impl Node<'_> {
+    fn label(&self, newline: &str) -> String {
+        match (&self.hex, self.kind) {
+            (_, NodeKind::Missing) => format!("{}{newline}(missing)", self.key),
+            (Some(hex), _) => format!("{}{newline}{hex}", self.key),
+            (None, _) => format!("{}{newline}(unresolved)", self.key),
+        }
+    }
+}
+
+/// Readable text color on a `hex` background.
+fn text_color(hex: &str) -> &'static str {
+    match Oklab::from_hex(hex) {
+        Some(lab) if lab.l < 0.6 => "#FFFFFF",
+        _ => "#000000",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PALETTE: &str = r##"
+[meta]
+name = "Graph"
+
+[colors.light]
+background = "#FFFFFF"
+
+[colors.dark]
+background = "#0E1117"
+surface = "colors.dark.background"
+
+[accents]
+info = "#3FA7D6"
+
+[ui.sidebar]
+background = "colors.dark.surface"
+border = "ui.sidebar.background"
+
+[ansi.light.normal]
+black="#000000"
+red="#000000"
+green="#000000"
+yellow="#000000"
+blue="#000000"
+magenta="#000000"
+cyan="#000000"
+white="#000000"
+
+[ansi.light.bright]
+black="#111111"
+red="#111111"
+green="#111111"
+yellow="#111111"
+blue="#111111"
+magenta="#111111"
+cyan="#111111"
+white="#111111"
+
+[ansi.dark.normal]
+black="colors.dark.background"
+red="#000000"
+green="#000000"
+yellow="#000000"
+blue="#000000"
+magenta="#000000"
+cyan="#000000"
+white="#000000"
+
+[ansi.dark.bright]
+black="#111111"
+red="#111111"
+green="#111111"
+yellow="#111111"
+blue="#111111"
+magenta="#111111"
+cyan="#111111"
+white="#111111"
+"##;
+
+    #[test]
+    fn collects_transitive_dependents() {
+        let graph = RefGraph::new(&Palette::parse(PALETTE).unwrap());
+
+        assert_eq!(
+            graph.direct_dependents("colors.dark.background"),
+            vec!["ansi.dark.normal.black", "colors.dark.surface"]
+        );
+        assert_eq!(
+            graph
+                .dependents("colors.dark.background")
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![
+                "ansi.dark.normal.black",
+                "colors.dark.surface",
+                "ui.sidebar.background",
+                "ui.sidebar.border",
+            ]
+        );
+        assert!(graph.dependents("accents.info").is_empty());
+    }
+
+    #[test]
+    fn renders_diagrams_with_colors_and_cycles() {
+        let cyclic = PALETTE.replace("info = \"#3FA7D6\"", "info = \"accents.info\"");
+        let graph = RefGraph::new(&Palette::parse(&cyclic).unwrap());
+        assert_eq!(graph.hex("ui.sidebar.border").as_deref(), Some("#0E1117"));
+        assert_eq!(graph.hex("accents.info"), None);
+
+        let dot = graph.render(GraphFormat::Dot);
+        assert!(
+            dot.contains(
+                "\"colors.dark.background\" [shape=box, style=\"filled\", \
+                 label=\"colors.dark.background\\n#0E1117\", \
+                 fillcolor=\"#0E1117\", fontcolor=\"#FFFFFF\"];"
+            ),
+            "{dot}"
+        );
+        assert!(
+            dot.contains("\"colors.dark.surface\" -> \"colors.dark.background\";"),
+            "{dot}"
+        );
+        assert!(
+            dot.contains("\"accents.info\" -> \"accents.info\" [color=\"red\", penwidth=2];"),
+            "{dot}"
+        );
+
+        let mermaid = graph.render(GraphFormat::Mermaid);
+        assert!(mermaid.starts_with("graph LR\n"), "{mermaid}");
+        assert!(
+            mermaid.contains("n0(\"accents.info<br/>(unresolved)\")"),
+            "{mermaid}"
+        );
+        assert!(mermaid.contains("linkStyle 0 stroke:red"), "{mermaid}");
+    }
+}
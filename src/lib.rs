@@ -0,0 +1,31 @@
+//! Veneer: load palettes described in TOML, resolve their references, and
+//! render Tera templates against them.
+//!
+//! ```no_run
+//! use veneer_theme::{Palette, Renderer};
+//!
+//! let resolved = Palette::load("veneer.toml")?.resolve()?;
+//! let renderer = Renderer::new(&resolved)?;
+//! let css = renderer.render_str("theme.css", ":root { --bg: {{ dark.background }}; }")?;
+//! # Ok::<(), veneer_theme::Error>(())
+//! ```
+
+pub mod blend;
+pub mod color;
+pub mod diff;
+pub mod dtcg;
+pub mod edit;
+mod error;
+pub mod export;
+pub mod extract;
+pub mod generate;
+pub mod graph;
+pub mod lint;
+pub mod palette;
+pub mod render;
+pub mod template;
+pub mod vars;
+
+pub use error::{Error, Result};
+pub use palette::{Palette, ResolvedPalette};
+pub use render::Renderer;
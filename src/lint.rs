@@ -0,0 +1,757 @@
+//! Palette lints: checks that don't stop a palette from resolving but point
+//! at keys or templates worth a second look.
+
+use std::{collections::BTreeMap, fmt};
+
+use serde_json::Value;
+
+use crate::{
+    Palette, Renderer, ResolvedPalette, Result,
+    color::{Lab, contrast_ratio, delta_e},
+    graph::RefGraph,
+    palette::{ANSI_NAMES, ColorRef, ResolvedAnsiRow, ResolvedAnsiScheme, Tone, flatten_tree},
+    template::{TemplateReads, reads_key},
+};
+
+/// One lint finding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    /// Rule name, e.g. `unused-key`.
+    pub rule: &'static str,
+    /// Palette key or template the finding is about.
+    pub subject: String,
+    pub message: String,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} [{}]", self.subject, self.message, self.rule)
+    }
+}
+
+/// Keys no template reads and no other key references.
+pub fn unused_keys(palette: &Palette, templates: &[TemplateReads]) -> Vec<Lint> {
+    let graph = RefGraph::new(palette);
+    let generated = palette.generated_keys();
+    graph
+        .iter()
+        .filter(|(key, _)| !generated.contains(*key))
+        .filter(|(key, _)| graph.direct_dependents(key).is_empty())
+        .filter(|(key, _)| {
+            !templates
+                .iter()
+                .flat_map(|t| &t.reads)
+                .any(|read| reads_key(read, key))
+        })
+        .map(|(key, _)| Lint {
+            rule: "unused-key",
+            subject: key.to_string(),
+            message: "not used by any template or key".to_string(),
+        })
+        .collect()
+}
+
+/// Template reads that don't exist in the render context, so rendering
+/// would fail.
+pub fn undefined_reads(
+    resolved: &ResolvedPalette,
+    templates: &[TemplateReads],
+) -> Result<Vec<Lint>> {
+    let context = Renderer::new(resolved)?.context().clone().into_json();
+    let mut lints = Vec::new();
+    for template in templates {
+        for read in &template.reads {
+            if lookup(&context, read).is_none() {
+                lints.push(Lint {
+                    rule: "undefined-var",
+                    subject: template.path.display().to_string(),
+                    message: format!("'{read}' is not defined by the palette"),
+                });
+            }
+        }
+    }
+    Ok(lints)
+}
+
+/// Top-level tables with a meaning of their own; any other table is a color
+/// group.
+const BUILT_IN_TABLES: [&str; 6] = ["meta", "colors", "accents", "ansi", "tokens", "vars"];
+
+/// Color groups named one edit away from a built-in table, e.g. `[accent]`,
+/// which are most likely typos that would otherwise pass as custom groups.
+pub fn group_names(palette: &Palette) -> Vec<Lint> {
+    palette
+        .groups
+        .keys()
+        .filter_map(|name| {
+            let table = BUILT_IN_TABLES
+                .iter()
+                .find(|table| one_edit_apart(name, table))?;
+            Some(Lint {
+                rule: "group-name",
+                subject: name.clone(),
+                message: format!("read as a color group; did you mean [{table}]?"),
+            })
+        })
+        .collect()
+}
+
+/// Whether one insertion, deletion or substitution turns `a` into `b`.
+fn one_edit_apart(a: &str, b: &str) -> bool {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if long.len() - short.len() > 1 {
+        return false;
+    }
+    let prefix = short.iter().zip(&long).take_while(|(x, y)| x == y).count();
+    if prefix == long.len() {
+        return false;
+    }
+    let skip = usize::from(short.len() == long.len());
+    short[prefix + skip..] == long[prefix + 1..]
+}
+
+/// Default CIEDE2000 distance under which two colors count as near-duplicates.
+pub const NEAR_DUPLICATE_THRESHOLD: f32 = 2.0;
+
+/// Sections checked for duplicate literals.
+const DUPLICATE_SECTIONS: [&str; 3] = ["colors", "accents", "ansi"];
+
+/// Literal colors in `colors`, `accents` and `ansi`, in that order, with
+/// their hex uppercased.
+fn literal_colors(palette: &Palette) -> Vec<(String, String)> {
+    // Derived colors and scale steps aren't written in the file.
+    let generated = palette.generated_keys();
+    let mut literals: Vec<(String, String)> = palette
+        .entries()
+        .into_iter()
+        .filter_map(|(key, cref)| match cref {
+            ColorRef::Hex(hex) => Some((key, hex.to_uppercase())),
+            ColorRef::Path(_) => None,
+        })
+        .filter(|(key, _)| {
+            let section = key.split('.').next().unwrap_or_default();
+            DUPLICATE_SECTIONS.contains(&section)
+        })
+        .filter(|(key, _)| !generated.contains(key))
+        .collect();
+    literals.sort_by_key(|(key, _)| {
+        let section = key.split('.').next().unwrap_or_default();
+        DUPLICATE_SECTIONS.iter().position(|s| *s == section)
+    });
+    literals
+}
+
+/// Keys repeating an earlier literal of the same tone, paired with the first
+/// `colors` or `accents` key that declared it. Rewriting each into a
+/// reference to its pair keeps the palette identical.
+///
+/// Light and dark keys never pair, even when they hold the same color, and
+/// ANSI slots are never canonical: `ansi.dark.normal.black` may point at
+/// `colors.dark.background`, but red matching bright black is a coincidence
+/// rather than a shared meaning.
+pub fn duplicate_fixes(palette: &Palette) -> Vec<(String, String)> {
+    let mut first = BTreeMap::<(Option<Tone>, String), String>::new();
+    let mut fixes = Vec::new();
+    for (key, hex) in literal_colors(palette) {
+        let scope = (Tone::of_key(&key), hex);
+        match first.get(&scope) {
+            Some(canonical) => fixes.push((key, canonical.clone())),
+            None if !key.starts_with("ansi.") => {
+                first.insert(scope, key);
+            }
+            None => {}
+        }
+    }
+    fixes
+}
+
+/// Literals that repeat an earlier literal of the same tone exactly; see
+/// [`duplicate_fixes`].
+pub fn duplicate_colors(palette: &Palette) -> Vec<Lint> {
+    duplicate_fixes(palette)
+        .into_iter()
+        .map(|(key, canonical)| Lint {
+            rule: "duplicate-color",
+            subject: key,
+            message: format!("same color as {canonical}; reference it instead"),
+        })
+        .collect()
+}
+
+/// Groups of distinct literals within `threshold` (CIEDE2000) of each
+/// other, linked transitively. As with [`duplicate_fixes`], only keys of the
+/// same tone are compared.
+pub fn near_duplicates(palette: &Palette, threshold: f32) -> Vec<Lint> {
+    let mut distinct: Vec<(String, String)> = Vec::new();
+    for (key, hex) in literal_colors(palette) {
+        let tone = Tone::of_key(&key);
+        if !distinct
+            .iter()
+            .any(|(seen_key, seen)| *seen == hex && Tone::of_key(seen_key) == tone)
+        {
+            distinct.push((key, hex));
+        }
+    }
+
+    // Single-linkage clustering: `cluster[i]` is the index of i's cluster.
+    let mut cluster: Vec<usize> = (0..distinct.len()).collect();
+    let mut max_distance = vec![0.0f32; distinct.len()];
+    for i in 0..distinct.len() {
+        for j in i + 1..distinct.len() {
+            if Tone::of_key(&distinct[i].0) != Tone::of_key(&distinct[j].0) {
+                continue;
+            }
+            let Some(distance) = delta_e(&distinct[i].1, &distinct[j].1) else {
+                continue;
+            };
+            if distance > threshold {
+                continue;
+            }
+            let (keep, merge) = (cluster[i].min(cluster[j]), cluster[i].max(cluster[j]));
+            for c in cluster.iter_mut().filter(|c| **c == merge) {
+                *c = keep;
+            }
+            max_distance[keep] = max_distance[keep].max(max_distance[merge]).max(distance);
+        }
+    }
+
+    (0..distinct.len())
+        .filter_map(|root| {
+            let members: Vec<String> = (0..distinct.len())
+                .filter(|&i| cluster[i] == root)
+                .map(|i| format!("{} {}", distinct[i].0, distinct[i].1))
+                .collect();
+            (members.len() > 1).then(|| Lint {
+                rule: "near-duplicate",
+                subject: distinct[root].0.clone(),
+                message: format!(
+                    "near-duplicate colors (max ΔE00 {:.2}): {}",
+                    max_distance[root],
+                    members.join(", ")
+                ),
+            })
+        })
+        .collect()
+}
+
+/// Default CIEDE2000 distance every pair of ANSI colors in a row must exceed.
+pub const ANSI_DISTINCT_THRESHOLD: f32 = 10.0;
+
+/// WCAG contrast ANSI white needs against the tone's background.
+const ANSI_MIN_CONTRAST: f32 = 4.5;
+
+/// Checks on both ANSI schemes:
+///
+/// - `ansi-bright`: each bright color from red to white is lighter (CIE L*)
+///   than its normal counterpart, and bright black moves from the
+///   background toward the text (lighter in a dark tone, darker in a light
+///   one).
+/// - `ansi-distinct`: the eight colors of each row are at least `threshold`
+///   apart (CIEDE2000).
+/// - `ansi-black-white`: as in the example palettes, `normal.black` sits on
+///   the background side in both tones and `normal.white` is the foreground,
+///   so white needs 4.5:1 contrast against `colors.<tone>.background` (or
+///   against black when there is no background key) and black must stand out
+///   less than white.
+pub fn ansi_schemes(resolved: &ResolvedPalette, threshold: f32) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    for tone in Tone::ALL {
+        let (scheme, colors) = match tone {
+            Tone::Light => (&resolved.ansi.light, &resolved.colors.light),
+            Tone::Dark => (&resolved.ansi.dark, &resolved.colors.dark),
+        };
+        let base = format!("ansi.{tone}");
+        let background = flatten_tree(colors)
+            .into_iter()
+            .find(|(key, _)| key == "background")
+            .map(|(_, hex)| hex);
+
+        lints.extend(bright_rule(scheme, &base, tone));
+        for (level, row) in [("normal", &scheme.normal), ("bright", &scheme.bright)] {
+            lints.extend(distinct_rule(row, &format!("{base}.{level}"), threshold));
+        }
+        lints.extend(black_white_rule(scheme, &base, background));
+    }
+    lints
+}
+
+fn bright_rule(scheme: &ResolvedAnsiScheme, base: &str, tone: Tone) -> Vec<Lint> {
+    let normal = scheme.normal.colors();
+    let bright = scheme.bright.colors();
+    let lint = |idx: usize, direction: &str| Lint {
+        rule: "ansi-bright",
+        subject: format!("{base}.bright.{}", ANSI_NAMES[idx]),
+        message: format!(
+            "{} (L* {:.1}) is not {direction} than {base}.normal.{} {} (L* {:.1})",
+            bright[idx],
+            lightness(bright[idx]),
+            ANSI_NAMES[idx],
+            normal[idx],
+            lightness(normal[idx])
+        ),
+    };
+
+    // Red through white.
+    let mut lints: Vec<Lint> = (1..8)
+        .filter(|&idx| lightness(bright[idx]) <= lightness(normal[idx]))
+        .map(|idx| lint(idx, "lighter"))
+        .collect();
+
+    // Normal black is the background, so bright black moves toward the
+    // text: darker in a light tone.
+    let (black, bright_black) = (lightness(normal[0]), lightness(bright[0]));
+    match tone {
+        Tone::Light if bright_black >= black => lints.insert(0, lint(0, "darker")),
+        Tone::Dark if bright_black <= black => lints.insert(0, lint(0, "lighter")),
+        _ => {}
+    }
+    lints
+}
+
+fn distinct_rule(row: &ResolvedAnsiRow, base: &str, threshold: f32) -> Vec<Lint> {
+    let colors = row.colors();
+    let mut lints = Vec::new();
+    for i in 0..colors.len() {
+        for j in i + 1..colors.len() {
+            let Some(distance) = delta_e(colors[i], colors[j]) else {
+                continue;
+            };
+            if distance < threshold {
+                lints.push(Lint {
+                    rule: "ansi-distinct",
+                    subject: format!("{base}.{}", ANSI_NAMES[i]),
+                    message: format!(
+                        "too close to {base}.{} (ΔE00 {distance:.1} < {threshold:.1})",
+                        ANSI_NAMES[j]
+                    ),
+                });
+            }
+        }
+    }
+    lints
+}
+
+fn black_white_rule(
+    scheme: &ResolvedAnsiScheme,
+    base: &str,
+    background: Option<&str>,
+) -> Vec<Lint> {
+    let (black, white) = (scheme.normal.black.as_str(), scheme.normal.white.as_str());
+    let lint = |name: &str, message: String| Lint {
+        rule: "ansi-black-white",
+        subject: format!("{base}.normal.{name}"),
+        message,
+    };
+
+    let Some(background) = background else {
+        return match contrast_ratio(black, white) {
+            Some(ratio) if ratio < ANSI_MIN_CONTRAST => vec![lint(
+                "white",
+                format!(
+                    "{white} has contrast {ratio:.2}:1 against black {black}; \
+                     needs {ANSI_MIN_CONTRAST}:1"
+                ),
+            )],
+            _ => Vec::new(),
+        };
+    };
+
+    let mut lints = Vec::new();
+    let on_background = |hex: &str| contrast_ratio(hex, background).unwrap_or(1.0);
+    let (black_ratio, white_ratio) = (on_background(black), on_background(white));
+    if white_ratio < ANSI_MIN_CONTRAST {
+        lints.push(lint(
+            "white",
+            format!(
+                "{white} has contrast {white_ratio:.2}:1 on background {background}; \
+                 needs {ANSI_MIN_CONTRAST}:1"
+            ),
+        ));
+    }
+    if black_ratio >= white_ratio {
+        lints.push(lint(
+            "black",
+            format!(
+                "{black} stands out more than white on background {background} \
+                 ({black_ratio:.2}:1 vs {white_ratio:.2}:1); black should sit near the background"
+            ),
+        ));
+    }
+    lints
+}
+
+/// CIE L* of a resolved color.
+fn lightness(hex: &str) -> f32 {
+    Lab::from_hex(hex).map_or(0.0, |lab| lab.l)
+}
+
+/// Follow a dotted path through objects and array indices.
+pub(crate) fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(value, |value, segment| match value {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::template;
+
+    const PALETTE: &str = r##"
+[meta]
+name = "Lint"
+
+[colors.light]
+background = "#FFFFFF"
+unused = "#123456"
+
+[colors.dark]
+background = "#0E1117"
+surface = "colors.dark.background"
+
+[accents]
+info = "#3FA7D6"
+
+[ansi.light.normal]
+black="#000000"
+red="#000000"
+green="#000000"
+yellow="#000000"
+blue="#000000"
+magenta="#000000"
+cyan="#000000"
+white="#000000"
+
+[ansi.light.bright]
+black="#111111"
+red="#111111"
+green="#111111"
+yellow="#111111"
+blue="#111111"
+magenta="#111111"
+cyan="#111111"
+white="#111111"
+
+[ansi.dark.normal]
+black="#000000"
+red="#000000"
+green="#000000"
+yellow="#000000"
+blue="#000000"
+magenta="#000000"
+cyan="#000000"
+white="#000000"
+
+[ansi.dark.bright]
+black="#111111"
+red="#111111"
+green="#111111"
+yellow="#111111"
+blue="#111111"
+magenta="#111111"
+cyan="#111111"
+white="#111111"
+"##;
+
+    /// The example palette from the README.
+    const README_PALETTE: &str = r##"
+[meta]
+name = "Veneer Demo"
+version = "0.1.0"                  # must be semver
+description = "A demo palette"
+author = "Jane Doe"
+license = "MIT"
+homepage = "https://github.com/ascarter/veneer-demo"
+publisher = "ascarter"             # VS Code publisher
+# identifier = "com.example.veneer-demo"  # reverse-DNS id; derived when unset
+
+[meta.extra]                       # free-form values for templates
+zed_schema = "https://zed.dev/schema/themes/v0.2.0.json"
+
+[colors.light]
+background = "#FFFFFF"
+primary    = "#2E73FF"
+text       = "#111111"
+
+[colors.dark]
+background = "#0E1117"
+primary    = "colors.light.primary"  # reference to another key
+text       = "#E6EDF3"
+
+[accents]
+info    = "#3FA7D6"
+warning = "#E6A700"
+
+[ansi.light.normal]
+black   = "colors.light.background"
+red     = "#CC241D"
+green   = "#98971A"
+yellow  = "#D79921"
+blue    = "#458588"
+magenta = "#B16286"
+cyan    = "#689D6A"
+white   = "colors.light.text"
+
+[ansi.light.bright]
+black   = "#282828"
+red     = "#FB4934"
+green   = "#B8BB26"
+yellow  = "#FABD2F"
+blue    = "#83A598"
+magenta = "#D3869B"
+cyan    = "#8EC07C"
+white   = "#FBF1C7"
+
+[ansi.dark.normal]
+black   = "colors.dark.background"
+red     = "#CC241D"
+green   = "#98971A"
+yellow  = "#D79921"
+blue    = "#458588"
+magenta = "#B16286"
+cyan    = "#689D6A"
+white   = "colors.dark.text"
+
+[ansi.dark.bright]
+black   = "#3C3836"
+red     = "#FB4934"
+green   = "#B8BB26"
+yellow  = "#FABD2F"
+blue    = "#83A598"
+magenta = "#D3869B"
+cyan    = "#8EC07C"
+white   = "#F9F5D7"
+
+[tokens]
+diff.added = { light = "ansi.light.normal.green", dark = "ansi.dark.bright.green" }
+syntax.comment = { light = "colors.light.text", dark = "colors.dark.text" }
+
+[ui.sidebar]
+background = "colors.dark.background"
+border     = "ui.sidebar.background"
+"##;
+
+    fn scanned(source: &str) -> Vec<TemplateReads> {
+        vec![TemplateReads {
+            path: PathBuf::from("theme.tera"),
+            reads: template::reads("theme.tera", source).unwrap(),
+        }]
+    }
+
+    #[test]
+    fn reports_keys_unused_by_templates_and_keys() {
+        let palette = Palette::parse(PALETTE).unwrap();
+        let templates = scanned(
+            "{{ light.background }} {{ dark.surface }} {{ accents.info }} \
+             {% for name, hex in ansi.dark.normal %}{{ hex | nearest_ansi256 }}{% endfor %}",
+        );
+        let lints = unused_keys(&palette, &templates);
+        assert_eq!(
+            lints,
+            vec![Lint {
+                rule: "unused-key",
+                subject: "colors.light.unused".into(),
+                message: "not used by any template or key".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn finds_duplicate_and_near_duplicate_literals() {
+        let palette = Palette::parse(
+            &PALETTE
+                .replace("unused = \"#123456\"", "unused = \"#ffffff\"")
+                .replacen("white=\"#000000\"", "white=\"#FFFFFF\"", 1)
+                .replace(
+                    "info = \"#3FA7D6\"",
+                    "info = \"#1E1E1E\"\nhint = \"#1F1E1E\"",
+                ),
+        )
+        .unwrap();
+
+        // The ANSI rows repeat #000000 and #111111 across slots and tones,
+        // and `ansi.light.normal.white` matches the light background.
+        assert_eq!(
+            duplicate_fixes(&palette),
+            vec![
+                (
+                    "colors.light.unused".to_string(),
+                    "colors.light.background".to_string()
+                ),
+                (
+                    "ansi.light.normal.white".to_string(),
+                    "colors.light.background".to_string()
+                ),
+            ]
+        );
+
+        let cross_tone =
+            Palette::parse(&PALETTE.replace("unused = \"#123456\"", "unused = \"#0e1117\""))
+                .unwrap();
+        assert!(duplicate_fixes(&cross_tone).is_empty());
+        let cross_tone =
+            Palette::parse(&PALETTE.replace("unused = \"#123456\"", "unused = \"#0f1117\""))
+                .unwrap();
+        assert!(near_duplicates(&cross_tone, NEAR_DUPLICATE_THRESHOLD).is_empty());
+
+        let near = near_duplicates(&palette, NEAR_DUPLICATE_THRESHOLD);
+        let clusters: Vec<&str> = near.iter().map(|l| l.message.as_str()).collect();
+        assert!(
+            clusters
+                .iter()
+                .any(|m| m.contains("accents.hint #1F1E1E, accents.info #1E1E1E")),
+            "{clusters:?}"
+        );
+    }
+
+    #[test]
+    fn checks_ansi_brightness_distinctness_and_contrast() {
+        // Blue and magenta nearly match, and white is dim on a dark background.
+        let dark_normal = r##"[ansi.dark.normal]
+black="#1D2021"
+red="#CC241D"
+green="#98971A"
+yellow="#D79921"
+blue="#458588"
+magenta="#478589"
+cyan="#689D6A"
+white="#3C3836"
+
+"##;
+        let start = PALETTE.find("[ansi.dark.normal]").unwrap();
+        let end = PALETTE.find("[ansi.dark.bright]").unwrap();
+        let palette = format!("{}{dark_normal}{}", &PALETTE[..start], &PALETTE[end..]);
+        let resolved = Palette::parse(&palette).unwrap().resolve().unwrap();
+        let lints: Vec<String> = ansi_schemes(&resolved, ANSI_DISTINCT_THRESHOLD)
+            .iter()
+            .filter(|lint| lint.subject.starts_with("ansi.dark.normal"))
+            .map(ToString::to_string)
+            .collect();
+
+        assert!(
+            lints
+                .iter()
+                .any(|l| l
+                    .starts_with("ansi.dark.normal.blue: too close to ansi.dark.normal.magenta")),
+            "{lints:#?}"
+        );
+        assert!(
+            lints
+                .iter()
+                .any(|l| l.starts_with("ansi.dark.normal.white: #3C3836 has contrast")),
+            "{lints:#?}"
+        );
+        assert!(
+            !lints
+                .iter()
+                .any(|l| l.contains("ansi.dark.normal.red: too close")),
+            "{lints:#?}"
+        );
+
+        let bright: Vec<Lint> = ansi_schemes(&resolved, ANSI_DISTINCT_THRESHOLD)
+            .into_iter()
+            .filter(|lint| lint.rule == "ansi-bright")
+            .collect();
+        assert!(
+            bright.iter().any(|l| l.subject == "ansi.dark.bright.red"),
+            "#111111 is darker than #CC241D: {bright:#?}"
+        );
+        assert!(!bright.iter().any(|l| l.subject == "ansi.light.bright.red"));
+    }
+
+    #[test]
+    fn flags_group_names_close_to_built_in_tables() {
+        let raw = format!(
+            "{PALETTE}\n[accent]\nlink = \"#3FA7D6\"\n\n\
+             [token.syntax]\ncomment = {{ light = \"#111111\", dark = \"#EEEEEE\" }}\n\n\
+             [ansii]\nred = \"#CC241D\"\n\n[ui]\nborder = \"#30363D\"\n\n\
+             [var]\nborder = \"#30363D\"\n"
+        );
+        let lints: Vec<String> = group_names(&Palette::parse(&raw).unwrap())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            lints,
+            vec![
+                "accent: read as a color group; did you mean [accents]? [group-name]",
+                "ansii: read as a color group; did you mean [ansi]? [group-name]",
+                "token: read as a color group; did you mean [tokens]? [group-name]",
+                "var: read as a color group; did you mean [vars]? [group-name]",
+            ]
+        );
+
+        assert!(one_edit_apart("colour", "color"));
+        assert!(one_edit_apart("metta", "meta"));
+        assert!(!one_edit_apart("colors", "colors"));
+        assert!(!one_edit_apart("ui", "vars"));
+        assert!(!one_edit_apart("tone", "tokens"));
+    }
+
+    #[test]
+    fn passes_the_readme_palette() {
+        let palette = Palette::parse(README_PALETTE).unwrap();
+        let resolved = palette.resolve().unwrap();
+        let mut lints = group_names(&palette);
+        lints.extend(duplicate_colors(&palette));
+        lints.extend(near_duplicates(&palette, NEAR_DUPLICATE_THRESHOLD));
+        lints.extend(ansi_schemes(&resolved, ANSI_DISTINCT_THRESHOLD));
+        assert!(lints.is_empty(), "{lints:#?}");
+
+        // Bright black moving toward the background is still reported.
+        let faded = README_PALETTE.replace("black   = \"#282828\"", "black   = \"#FFFFFF\"");
+        let resolved = Palette::parse(&faded).unwrap().resolve().unwrap();
+        let bright: Vec<String> = ansi_schemes(&resolved, ANSI_DISTINCT_THRESHOLD)
+            .iter()
+            .filter(|lint| lint.rule == "ansi-bright")
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            bright,
+            vec![
+                "ansi.light.bright.black: #FFFFFF (L* 100.0) is not darker than \
+                 ansi.light.normal.black #FFFFFF (L* 100.0) [ansi-bright]"
+            ]
+        );
+
+        // So is a dark bright white dimmer than the text it brightens.
+        let dim = README_PALETTE.replace("white   = \"#F9F5D7\"", "white   = \"#EBDBB2\"");
+        let resolved = Palette::parse(&dim).unwrap().resolve().unwrap();
+        let bright: Vec<String> = ansi_schemes(&resolved, ANSI_DISTINCT_THRESHOLD)
+            .iter()
+            .filter(|lint| lint.rule == "ansi-bright")
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            bright,
+            vec![
+                "ansi.dark.bright.white: #EBDBB2 (L* 87.7) is not lighter than \
+                 ansi.dark.normal.white #E6EDF3 (L* 93.4) [ansi-bright]"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_template_reads_missing_from_palette() {
+        let resolved = Palette::parse(PALETTE).unwrap().resolve().unwrap();
+        let templates = scanned(
+            "{{ dark.backgruond }} {{ light.background }} {{ ansi.dark.extended[300] }} \
+             {{ meta.slug }} {% for k, v in accents %}{{ v }}{% endfor %}",
+        );
+        let lints = undefined_reads(&resolved, &templates).unwrap();
+        let messages: Vec<&str> = lints.iter().map(|l| l.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "'ansi.dark.extended.300' is not defined by the palette",
+                "'dark.backgruond' is not defined by the palette",
+            ]
+        );
+    }
+}
@@ -1,11 +1,24 @@
 mod cli;
-mod palette;
-mod render;
+mod deps;
+mod editor;
+mod preview;
 mod show;
 
-use anyhow::Result;
+use std::{fs, path::Path, process};
+
+use anyhow::{Context, Result, bail};
 use clap::Parser;
 use cli::{Cli, Command};
+use veneer_theme::{
+    Error, Palette, Renderer, blend, diff, dtcg,
+    edit::{self, PaletteDocument},
+    export, extract,
+    generate::{self, GenerateOptions},
+    graph::RefGraph,
+    lint,
+    palette::ColorRef,
+    render, template,
+};
 
 fn main() {
     if let Err(err) = run() {
@@ -18,16 +31,438 @@ fn run() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Command::Build { src, dest, palette } => {
-            render::build(&palette, &src, dest.as_ref())?;
+        Command::Build {
+            src,
+            dest,
+            palette,
+            strict,
+        } => {
+            if strict {
+                check_strict(&palette, &src)?;
+            }
+            render::build(&palette, &src, dest.as_deref())?;
         }
-        Command::Check { palette, template } => {
+        Command::Check {
+            palette,
+            template,
+            strict,
+        } => {
+            if strict {
+                check_strict(&palette, &template)?;
+            }
             render::check_single(&palette, &template)?;
         }
-        Command::Show { palette } => {
-            show::run(&palette)?;
+        Command::Show {
+            palette,
+            trace,
+            preview,
+            mappings,
+        } => {
+            if preview.is_empty() {
+                show::run(&palette, trace)?;
+            } else {
+                show::preview(&palette, &preview, &mappings.into_iter().collect())?;
+            }
+        }
+        Command::Edit { palette } => {
+            editor::run(&palette)?;
+        }
+        Command::Why { palette, path } => {
+            show::why(&palette, &path)?;
+        }
+        Command::Deps {
+            palette,
+            path,
+            templates,
+        } => {
+            deps::run(&palette, &path, templates.as_deref())?;
+        }
+        Command::Lint {
+            palette,
+            templates,
+            threshold,
+            ansi_threshold,
+            fix,
+        } => {
+            if fix {
+                fix_duplicates(&palette)?;
+            }
+            lint(&palette, templates.as_deref(), threshold, ansi_threshold)?;
+        }
+        Command::Diff {
+            old,
+            new,
+            rev,
+            palette,
+            json,
+        } => {
+            let (old_label, old_palette, new_label, new_palette) = match (rev, old, new) {
+                (Some(rev), _, _) => {
+                    let raw = git_show(&rev, &palette)?;
+                    let old = Palette::parse(&raw)
+                        .with_context(|| format!("parsing {} at {rev}", palette.display()))?;
+                    let label = format!("{} ({rev})", palette.display());
+                    let new_label = palette.display().to_string();
+                    (label, old, new_label, Palette::load(&palette)?)
+                }
+                (None, Some(old), Some(new)) => (
+                    old.display().to_string(),
+                    Palette::load(&old)?,
+                    new.display().to_string(),
+                    Palette::load(&new)?,
+                ),
+                _ => bail!("pass two palette files, or --rev"),
+            };
+            let changes = diff::diff(&old_palette.resolve()?, &new_palette.resolve()?);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&changes)?);
+            } else {
+                show::print_diff(&old_label, &new_label, &changes);
+            }
+        }
+        Command::Graph {
+            palette,
+            format,
+            output,
+        } => {
+            let graph = RefGraph::new(&Palette::load(&palette)?);
+            write_output(output.as_deref(), &graph.render(format))?;
+        }
+        Command::Resolve {
+            palette,
+            format,
+            tone,
+            flat,
+        } => {
+            let resolved = Palette::load(&palette)?.resolve()?;
+            print!("{}", export::render(&resolved, format, tone, flat)?);
+        }
+        Command::Export { palette, output } => {
+            let doc = dtcg::export(&Palette::load(&palette)?)?;
+            let json = serde_json::to_string_pretty(&doc)?;
+            write_output(output.as_deref(), &format!("{json}\n"))?;
+        }
+        Command::Import { tokens, output } => {
+            let raw = fs::read_to_string(&tokens)
+                .with_context(|| format!("reading {}", tokens.display()))?;
+            let palette =
+                dtcg::import(&raw).with_context(|| format!("importing {}", tokens.display()))?;
+            write_output(output.as_deref(), &palette.to_toml()?)?;
+        }
+        Command::New {
+            seed,
+            background,
+            foreground,
+            name,
+            output,
+        } => {
+            if let Some(path) = &output {
+                if path.exists() {
+                    bail!("{} already exists", path.display());
+                }
+            }
+            let toml = generate::generate(&GenerateOptions {
+                name,
+                seed,
+                background,
+                foreground,
+                swatches: Vec::new(),
+            })?;
+            write_output(output.as_deref(), &toml)?;
+        }
+        Command::Derive { palette } => {
+            write_derived(&palette)?;
+        }
+        Command::Get { path, palette } => {
+            let resolved = Palette::load(&palette)?.resolve()?;
+            let trace = resolved
+                .trace
+                .get(&path)
+                .ok_or_else(|| Error::MissingPath(path.clone()))?;
+            println!("{}", trace.hex);
+        }
+        Command::Set {
+            path,
+            value,
+            palette,
+        } => {
+            let raw = fs::read_to_string(&palette)
+                .with_context(|| format!("reading {}", palette.display()))?;
+            let edited = edit::set_color(&raw, &path, &value)
+                .with_context(|| format!("setting {path} in {}", palette.display()))?;
+            fs::write(&palette, edited)
+                .with_context(|| format!("writing {}", palette.display()))?;
+            println!("{path} = {value}");
+        }
+        Command::Fmt { palette, check } => {
+            format_palette(&palette, check)?;
+        }
+        Command::Blend {
+            from,
+            to,
+            steps,
+            out_dir,
+        } => {
+            write_blends(&from, &to, steps, &out_dir)?;
+        }
+        Command::Extract {
+            image,
+            colors,
+            name,
+            output,
+        } => {
+            extract(&image, colors, name, &output)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Print strict-mode issues per template and fail if there are any.
+fn check_strict(palette_path: &Path, src: &Path) -> Result<()> {
+    let resolved = Palette::load(palette_path)?.resolve()?;
+    let renderer = Renderer::new(&resolved)?;
+
+    let mut total = 0;
+    for path in render::template_files(src)? {
+        let source =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        let issues = renderer.strict_issues(&path.display().to_string(), &source)?;
+        if issues.is_empty() {
+            continue;
+        }
+        println!("{}: {} issue(s)", path.display(), issues.len());
+        for issue in &issues {
+            println!("  {issue}");
+        }
+        total += issues.len();
+    }
+    if total > 0 {
+        bail!("strict mode found {total} issue(s)");
+    }
+    Ok(())
+}
+
+/// Rewrite exact duplicate literals as references, preserving the file's layout.
+fn fix_duplicates(palette_path: &Path) -> Result<()> {
+    let fixes = lint::duplicate_fixes(&Palette::load(palette_path)?);
+    if fixes.is_empty() {
+        return Ok(());
+    }
+
+    let raw = fs::read_to_string(palette_path)
+        .with_context(|| format!("reading {}", palette_path.display()))?;
+    let mut doc: PaletteDocument = raw.parse()?;
+    for (key, canonical) in &fixes {
+        doc.set_color(key, &ColorRef::Path(canonical.clone()))?;
+        println!("{key} -> {canonical}");
+    }
+    fs::write(palette_path, doc.to_string())
+        .with_context(|| format!("writing {}", palette_path.display()))?;
+    println!(
+        "Rewrote {} duplicate(s) as references in {}\n",
+        fixes.len(),
+        palette_path.display()
+    );
+    Ok(())
+}
+
+/// Print lint findings grouped by rule and fail if there are any.
+fn lint(
+    palette_path: &Path,
+    templates: Option<&Path>,
+    threshold: f32,
+    ansi_threshold: f32,
+) -> Result<()> {
+    let palette = Palette::load(palette_path)?;
+    let resolved = palette.resolve()?;
+
+    let mut lints = lint::group_names(&palette);
+    lints.extend(lint::duplicate_colors(&palette));
+    lints.extend(lint::near_duplicates(&palette, threshold));
+    lints.extend(lint::ansi_schemes(&resolved, ansi_threshold));
+    if let Some(src) = templates {
+        let scanned = template::scan(src)?;
+        lints.extend(lint::unused_keys(&palette, &scanned));
+        lints.extend(lint::undefined_reads(&resolved, &scanned)?);
+    }
+
+    let mut rules = Vec::new();
+    for found in &lints {
+        if !rules.contains(&found.rule) {
+            rules.push(found.rule);
+        }
+    }
+    for rule in rules {
+        let found: Vec<_> = lints.iter().filter(|found| found.rule == rule).collect();
+        println!("{rule} ({})", found.len());
+        for found in found {
+            println!("  {}: {}", found.subject, found.message);
         }
+        println!();
+    }
+    if !lints.is_empty() {
+        bail!(
+            "{} lint issue(s) in {}",
+            lints.len(),
+            palette_path.display()
+        );
+    }
+    println!("No lint issues in {}", palette_path.display());
+    Ok(())
+}
+
+/// Insert derived colors into the palette file as literals, for review.
+fn write_derived(palette_path: &Path) -> Result<()> {
+    let palette = Palette::load(palette_path)?;
+    if palette.colors.derived.is_empty() {
+        println!("Nothing to derive in {}", palette_path.display());
+        return Ok(());
     }
 
+    let raw = fs::read_to_string(palette_path)
+        .with_context(|| format!("reading {}", palette_path.display()))?;
+    let mut doc: PaletteDocument = raw.parse()?;
+    let entries = palette.entries();
+    for key in &palette.colors.derived {
+        let (_, value) = entries
+            .iter()
+            .find(|(path, _)| path == key)
+            .expect("derived keys are palette entries");
+        doc.insert_color(key, value)?;
+        println!("{key} = {}", value.as_str());
+    }
+    fs::write(palette_path, doc.to_string())
+        .with_context(|| format!("writing {}", palette_path.display()))?;
+    println!(
+        "Wrote {} derived color(s) to {}",
+        palette.colors.derived.len(),
+        palette_path.display()
+    );
     Ok(())
 }
+
+/// Rewrite the palette file in canonical form, or with `check` only report
+/// whether it already is.
+fn format_palette(palette_path: &Path, check: bool) -> Result<()> {
+    // Only well-formed palettes are rewritten.
+    Palette::load(palette_path)?;
+    let raw = fs::read_to_string(palette_path)
+        .with_context(|| format!("reading {}", palette_path.display()))?;
+    let mut doc: PaletteDocument = raw.parse()?;
+    doc.format();
+    let formatted = doc.to_string();
+
+    if formatted == raw {
+        println!("{} is formatted", palette_path.display());
+    } else if check {
+        bail!(
+            "{} is not formatted; run `veneer fmt`",
+            palette_path.display()
+        );
+    } else {
+        fs::write(palette_path, formatted)
+            .with_context(|| format!("writing {}", palette_path.display()))?;
+        println!("Formatted {}", palette_path.display());
+    }
+    Ok(())
+}
+
+/// Write `steps` blends of `from` into `to` to `out_dir`, refusing to
+/// overwrite any existing file.
+fn write_blends(from: &Path, to: &Path, steps: usize, out_dir: &Path) -> Result<()> {
+    if steps == 0 {
+        bail!("--steps must be at least 1");
+    }
+    let read = |path: &Path| {
+        fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))
+    };
+    let stem = |path: &Path| {
+        path.file_stem().map_or_else(
+            || "palette".into(),
+            |stem| stem.to_string_lossy().into_owned(),
+        )
+    };
+    let blends = blend::blend(&read(from)?, &read(to)?, steps)?;
+
+    // Step numbers rather than percentages, which repeat past 100 steps;
+    // zero-padded so the files sort in order.
+    let width = steps.to_string().len();
+    let paths: Vec<_> = (1..=blends.len())
+        .map(|i| out_dir.join(format!("{}-{}-{i:0width$}.toml", stem(from), stem(to))))
+        .collect();
+    if let Some(existing) = paths.iter().find(|path| path.exists()) {
+        bail!("{} already exists", existing.display());
+    }
+    fs::create_dir_all(out_dir).with_context(|| format!("creating {}", out_dir.display()))?;
+    for (blend, path) in blends.iter().zip(&paths) {
+        fs::write(path, &blend.toml).with_context(|| format!("writing {}", path.display()))?;
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+/// Cluster an image's colors, preview the proposed palette, then write it.
+fn extract(image: &Path, colors: usize, name: Option<String>, output: &Path) -> Result<()> {
+    if output.exists() {
+        bail!("{} already exists", output.display());
+    }
+    let swatches = extract::dominant_colors(&extract::load_pixels(image)?, colors);
+    let name = name.unwrap_or_else(|| {
+        image
+            .file_stem()
+            .map_or_else(|| "Untitled".into(), |stem| stem.to_string_lossy().into())
+    });
+    let toml = extract::propose(&name, &image.display().to_string(), &swatches)?;
+
+    show::print_swatches("Image colors", &swatches);
+    show::print_palette(output, &Palette::parse(&toml)?.resolve()?, false);
+    fs::write(output, toml).with_context(|| format!("writing {}", output.display()))?;
+    println!("Wrote {}", output.display());
+    Ok(())
+}
+
+/// A file's contents at a git revision, via `git show <rev>:./<file>` run
+/// in the file's directory.
+fn git_show(rev: &str, path: &Path) -> Result<String> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = path
+        .file_name()
+        .with_context(|| format!("{} is not a file", path.display()))?;
+    let output = process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .arg("show")
+        .arg(format!("{rev}:./{}", name.to_string_lossy()))
+        .output()
+        .context("running git")?;
+    if !output.status.success() {
+        bail!(
+            "git show {rev}:{} failed: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    String::from_utf8(output.stdout).context("palette is not UTF-8")
+}
+
+/// Write command output to a file, or stdout when no path is given.
+fn write_output(path: Option<&Path>, contents: &str) -> Result<()> {
+    match path {
+        Some(path) => {
+            fs::write(path, contents).with_context(|| format!("writing {}", path.display()))
+        }
+        None => {
+            print!("{contents}");
+            Ok(())
+        }
+    }
+}
+
+/// The test palette `palette::TEST_PALETTE` also reads, for the command
+/// modules' tests.
+#[cfg(test)]
+const TEST_PALETTE: &str = include_str!("../tests/fixtures/palette.toml");
@@ -1,46 +1,215 @@
 use std::{
-    collections::{BTreeMap, HashMap},
-    fs,
-    path::PathBuf,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt, fs,
+    path::Path,
+    str::FromStr,
 };
 
-use anyhow::{Context, Result, bail};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Deserialize)]
+use crate::{
+    Error, Result,
+    color::{Oklab, invert_lightness, tonal_scale},
+    graph::RefGraph,
+    vars::{ResolvedVars, Vars, resolve_vars},
+};
+
+/// Light or dark variant of a palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tone {
+    Light,
+    Dark,
+}
+
+impl Tone {
+    pub const ALL: [Tone; 2] = [Tone::Light, Tone::Dark];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Tone::Light => "light",
+            Tone::Dark => "dark",
+        }
+    }
+
+    /// The tone a key belongs to through its path: `colors.<tone>.*`,
+    /// `ansi.<tone>.*` and `tokens.*.<tone>`.
+    pub fn of_key(key: &str) -> Option<Tone> {
+        let mut parts = key.split('.');
+        let tone = match parts.next()? {
+            "colors" | "ansi" => parts.next()?,
+            "tokens" => key.rsplit('.').next()?,
+            _ => return None,
+        };
+        tone.parse().ok()
+    }
+}
+
+impl fmt::Display for Tone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Tone {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "light" => Ok(Tone::Light),
+            "dark" => Ok(Tone::Dark),
+            other => Err(Error::InvalidTone(other.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Palette {
     pub meta: Meta,
     pub colors: Colors,
-    pub accents: BTreeMap<String, ColorRef>,
+    pub accents: ColorTree,
     pub ansi: Ansi,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tokens: BTreeMap<String, TokenNode>,
+    #[serde(default, skip_serializing_if = "Vars::is_empty")]
+    pub vars: Vars,
+    /// Any other top-level table, e.g. `[ui.sidebar]` or `[syntax]`.
+    #[serde(flatten)]
+    pub groups: BTreeMap<String, ColorTree>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Meta {
     pub name: String,
+    /// Semantic version, e.g. `1.2.0`.
     pub version: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub license: Option<String>,
+    pub homepage: Option<String>,
+    /// VS Code marketplace publisher.
+    pub publisher: Option<String>,
+    /// Reverse-DNS identifier (e.g. an Xcode bundle id); derived when unset.
+    pub identifier: Option<String>,
+    /// Free-form values for templates.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, toml::Value>,
+}
+
+impl Meta {
+    /// Lowercase, dash-separated name, e.g. `Veneer Demo` -> `veneer-demo`.
+    pub fn slug(&self) -> String {
+        let mut slug = String::new();
+        for ch in self.name.chars() {
+            if ch.is_ascii_alphanumeric() {
+                slug.push(ch.to_ascii_lowercase());
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        slug.trim_end_matches('-').to_string()
+    }
+
+    /// `identifier` if set, otherwise derived from `homepage` (reversed host
+    /// plus first path segment) or `publisher`, followed by the slug.
+    pub fn reverse_dns_id(&self) -> String {
+        if let Some(id) = &self.identifier {
+            return id.clone();
+        }
+
+        let mut parts = Vec::new();
+        if let Some(homepage) = &self.homepage {
+            let rest = homepage
+                .split_once("://")
+                .map_or(homepage.as_str(), |(_, r)| r);
+            let mut segments = rest.split('/').filter(|s| !s.is_empty());
+            if let Some(host) = segments.next() {
+                let host = host.split(':').next().unwrap_or(host);
+                parts.extend(
+                    host.rsplit('.')
+                        .filter(|s| *s != "www")
+                        .map(str::to_lowercase),
+                );
+            }
+            if let Some(owner) = segments.next() {
+                parts.push(owner.to_lowercase());
+            }
+        } else if let Some(publisher) = &self.publisher {
+            parts.push(publisher.to_lowercase());
+        }
+        parts.push(self.slug());
+        parts.join(".")
+    }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Colors {
-    pub light: BTreeMap<String, ColorRef>,
-    pub dark: BTreeMap<String, ColorRef>,
+    pub light: ColorTree,
+    pub dark: ColorTree,
+    /// Keys filled in by `derive = "<other tone>"`, as dotted paths. They hold
+    /// computed literals, and [`Palette::to_toml`] writes them out as such.
+    #[serde(skip)]
+    pub derived: Vec<String>,
+}
+
+impl Colors {
+    pub fn tree(&self, tone: Tone) -> &ColorTree {
+        match tone {
+            Tone::Light => &self.light,
+            Tone::Dark => &self.dark,
+        }
+    }
+
+    fn tree_mut(&mut self, tone: Tone) -> &mut ColorTree {
+        match tone {
+            Tone::Light => &mut self.light,
+            Tone::Dark => &mut self.dark,
+        }
+    }
+}
+
+/// A table of named colors, possibly nested into further groups.
+pub type ColorTree = BTreeMap<String, ColorNode>;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ColorNode {
+    Color(ColorRef),
+    Scale(Scale),
+    Group(ColorTree),
+}
+
+/// Tonal scale, written `blue = { scale = "#2E73FF", steps = [50, 100, 900] }`.
+/// Each step becomes a key (`blue.50`) when the palette is parsed.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scale {
+    /// Base color giving the scale its hue and chroma.
+    pub scale: ColorRef,
+    /// Increasing steps from 0 (lightest) to 1000 (darkest).
+    pub steps: Vec<u16>,
+    /// Generated colors in step order, keyed by step.
+    #[serde(skip)]
+    pub colors: Vec<(String, ColorRef)>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct Ansi {
     pub light: AnsiScheme,
     pub dark: AnsiScheme,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct AnsiScheme {
     pub normal: AnsiRow,
     pub bright: AnsiRow,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct AnsiRow {
     pub black: ColorRef,
     pub red: ColorRef,
@@ -52,6 +221,37 @@ pub struct AnsiRow {
     pub white: ColorRef,
 }
 
+impl AnsiRow {
+    /// `(name, color)` pairs in ANSI index order.
+    pub fn entries(&self) -> [(&'static str, &ColorRef); 8] {
+        [
+            ("black", &self.black),
+            ("red", &self.red),
+            ("green", &self.green),
+            ("yellow", &self.yellow),
+            ("blue", &self.blue),
+            ("magenta", &self.magenta),
+            ("cyan", &self.cyan),
+            ("white", &self.white),
+        ]
+    }
+}
+
+/// Semantic token tree: groups nest until a leaf maps each tone to a color.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum TokenNode {
+    Token(Token),
+    Group(BTreeMap<String, TokenNode>),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Token {
+    pub light: ColorRef,
+    pub dark: ColorRef,
+}
+
 /// Color references: either literal hex (#RRGGBB) or a dotted path to another key.
 #[derive(Debug, Clone)]
 pub enum ColorRef {
@@ -59,6 +259,23 @@ pub enum ColorRef {
     Path(String),
 }
 
+impl ColorRef {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ColorRef::Hex(s) | ColorRef::Path(s) => s,
+        }
+    }
+}
+
+impl Serialize for ColorRef {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
 impl<'de> Deserialize<'de> for ColorRef {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -77,14 +294,117 @@ impl<'de> Deserialize<'de> for ColorRef {
 pub struct ResolvedPalette {
     pub meta: Meta,
     pub colors: ResolvedColors,
-    pub accents: BTreeMap<String, String>,
+    pub accents: ResolvedTree,
     pub ansi: ResolvedAnsi,
+    pub tokens: BTreeMap<String, ResolvedTokenNode>,
+    pub vars: ResolvedVars,
+    #[serde(flatten)]
+    pub groups: BTreeMap<String, ResolvedTree>,
+    /// How each palette key was resolved, keyed by dotted path.
+    #[serde(skip)]
+    pub trace: BTreeMap<String, Trace>,
+}
+
+/// Resolution of one palette key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    /// Paths followed to reach a literal, in order; empty for a literal key.
+    pub chain: Vec<String>,
+    pub hex: String,
+}
+
+impl ResolvedPalette {
+    /// Keys whose resolution passes through `path`, directly or transitively.
+    pub fn dependents(&self, path: &str) -> Vec<&str> {
+        let graph = RefGraph::from_trace(&self.trace);
+        let dependents = graph.dependents(path);
+        self.trace
+            .keys()
+            .map(String::as_str)
+            .filter(|key| dependents.contains(key))
+            .collect()
+    }
+
+    /// Tokens flattened to dotted names (e.g. `syntax.comment`).
+    pub fn token_entries(&self) -> Vec<(String, &ResolvedToken)> {
+        fn walk<'a>(
+            nodes: &'a BTreeMap<String, ResolvedTokenNode>,
+            prefix: &str,
+            out: &mut Vec<(String, &'a ResolvedToken)>,
+        ) {
+            for (k, node) in nodes {
+                let name = if prefix.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{prefix}.{k}")
+                };
+                match node {
+                    ResolvedTokenNode::Token(token) => out.push((name, token)),
+                    ResolvedTokenNode::Group(children) => walk(children, &name, out),
+                }
+            }
+        }
+
+        let mut out = Vec::new();
+        walk(&self.tokens, "", &mut out);
+        out
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ResolvedColors {
-    pub light: BTreeMap<String, String>,
-    pub dark: BTreeMap<String, String>,
+    pub light: ResolvedTree,
+    pub dark: ResolvedTree,
+}
+
+pub type ResolvedTree = BTreeMap<String, ResolvedNode>;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ResolvedNode {
+    Color(String),
+    Scale(ResolvedScale),
+    Group(ResolvedTree),
+}
+
+/// Resolved tonal scale: `(step, hex)` pairs in step order, serialized as a
+/// map in that order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedScale(pub Vec<(String, String)>);
+
+impl Serialize for ResolvedScale {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_map(self.0.iter().map(|(step, hex)| (step, hex)))
+    }
+}
+
+/// Flatten a resolved tree into `(dotted key, hex)` pairs.
+pub fn flatten_tree(tree: &ResolvedTree) -> Vec<(String, &str)> {
+    fn walk<'a>(tree: &'a ResolvedTree, prefix: &str, out: &mut Vec<(String, &'a str)>) {
+        for (k, node) in tree {
+            let name = if prefix.is_empty() {
+                k.clone()
+            } else {
+                format!("{prefix}.{k}")
+            };
+            match node {
+                ResolvedNode::Color(hex) => out.push((name, hex)),
+                ResolvedNode::Scale(scale) => {
+                    for (step, hex) in &scale.0 {
+                        out.push((format!("{name}.{step}"), hex));
+                    }
+                }
+                ResolvedNode::Group(children) => walk(children, &name, out),
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(tree, "", &mut out);
+    out
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -97,6 +417,19 @@ pub struct ResolvedAnsi {
 pub struct ResolvedAnsiScheme {
     pub normal: ResolvedAnsiRow,
     pub bright: ResolvedAnsiRow,
+    /// Generated xterm colors 16–255; entry `i` is color index `16 + i`.
+    pub extended: Vec<String>,
+}
+
+impl ResolvedAnsiScheme {
+    /// Look up any xterm color index (0–255) in this scheme.
+    pub fn index(&self, idx: usize) -> Option<&str> {
+        match idx {
+            0..=7 => Some(self.normal.colors()[idx]),
+            8..=15 => Some(self.bright.colors()[idx - 8]),
+            _ => self.extended.get(idx - 16).map(String::as_str),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -111,39 +444,163 @@ pub struct ResolvedAnsiRow {
     pub white: String,
 }
 
-pub fn load_palette(path: &PathBuf) -> Result<Palette> {
-    let raw = fs::read_to_string(path)
-        .with_context(|| format!("reading palette file {}", path.display()))?;
-    let palette: Palette =
-        toml::from_str(&raw).with_context(|| format!("parsing TOML {}", path.display()))?;
-    validate_palette(&palette)?;
-    Ok(palette)
+/// ANSI color names in index order.
+pub const ANSI_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+impl ResolvedAnsiRow {
+    /// Colors in ANSI index order (black through white).
+    pub fn colors(&self) -> [&str; 8] {
+        [
+            &self.black,
+            &self.red,
+            &self.green,
+            &self.yellow,
+            &self.blue,
+            &self.magenta,
+            &self.cyan,
+            &self.white,
+        ]
+    }
 }
 
-pub fn resolve_palette(palette: &Palette) -> Result<ResolvedPalette> {
-    let mut memo = HashMap::<String, String>::new();
-    let mut stack = Vec::<String>::new();
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum ResolvedTokenNode {
+    Token(ResolvedToken),
+    Group(BTreeMap<String, ResolvedTokenNode>),
+}
 
-    let mut resolve_color = |label: &str, cref: &ColorRef| -> Result<String> {
-        match cref {
-            ColorRef::Hex(raw) => normalize_hex(raw),
-            ColorRef::Path(path) => resolve_path(palette, path, &mut memo, &mut stack)
-                .with_context(|| format!("resolving {label} -> {path}")),
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedToken {
+    pub light: String,
+    pub dark: String,
+}
+
+impl Palette {
+    /// Read, parse and validate a palette file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let raw = fs::read_to_string(path).map_err(|source| Error::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::parse(&raw).map_err(|err| match err {
+            Error::Toml(source) => Error::ParseToml {
+                path: path.to_path_buf(),
+                source,
+            },
+            other => other,
+        })
+    }
+
+    /// Parse and validate palette TOML, filling in derived color variants.
+    pub fn parse(raw: &str) -> Result<Self> {
+        let mut palette: Palette = toml::from_str(raw).map_err(Error::Toml)?;
+        let modes = take_derive_modes(&mut palette)?;
+        validate_palette(&palette)?;
+        expand_scales(&mut palette, true)?;
+        derive_colors(&mut palette, &modes)?;
+        // Scales based on derived colors.
+        expand_scales(&mut palette, false)?;
+        Ok(palette)
+    }
+
+    /// Resolve every reference into a concrete hex value.
+    pub fn resolve(&self) -> Result<ResolvedPalette> {
+        resolve_palette(self)
+    }
+
+    /// Every color key with its raw value, as `(dotted path, value)` pairs.
+    /// Keys containing `.`, which only a hand-built palette can have, are
+    /// left out.
+    pub fn entries(&self) -> Vec<(String, ColorRef)> {
+        let mut out = Vec::new();
+        // `visit_refs` only fails when the visitor does.
+        let _ = visit_refs(self, &mut |label, cref| {
+            out.push((label.to_string(), cref.clone()));
+            Ok(())
+        });
+        out
+    }
+
+    /// Keys computed when the palette was parsed rather than written in the
+    /// file: derived colors and tonal scale steps.
+    pub fn generated_keys(&self) -> BTreeSet<String> {
+        let mut out: BTreeSet<String> = self.colors.derived.iter().cloned().collect();
+        for (label, scale) in self.scales() {
+            out.extend(
+                scale
+                    .colors
+                    .iter()
+                    .map(|(step, _)| format!("{label}.{step}")),
+            );
+        }
+        out
+    }
+
+    /// Every tonal scale with its dotted path (`colors.light.blue`),
+    /// including those a deriving tone shares with its source.
+    pub fn scales(&self) -> Vec<(String, &Scale)> {
+        fn walk<'a>(tree: &'a ColorTree, base: &str, out: &mut Vec<(String, &'a Scale)>) {
+            for (k, node) in tree {
+                let label = format!("{base}.{k}");
+                match node {
+                    ColorNode::Scale(scale) => out.push((label, scale)),
+                    ColorNode::Group(children) => walk(children, &label, out),
+                    ColorNode::Color(_) => {}
+                }
+            }
         }
-    };
 
-    let mut colors_light = BTreeMap::new();
-    for (k, v) in &palette.colors.light {
-        colors_light.insert(k.clone(), resolve_color(&format!("colors.light.{k}"), v)?);
+        let mut out = Vec::new();
+        for (base, tree) in color_trees(self) {
+            walk(tree, &base, &mut out);
+        }
+        out
     }
-    let mut colors_dark = BTreeMap::new();
-    for (k, v) in &palette.colors.dark {
-        colors_dark.insert(k.clone(), resolve_color(&format!("colors.dark.{k}"), v)?);
+
+    /// Serialize back to palette TOML.
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string(self).map_err(|source| Error::Serialize {
+            format: "toml",
+            source: Box::new(source),
+        })
     }
+}
+
+fn resolve_palette(palette: &Palette) -> Result<ResolvedPalette> {
+    let mut memo = HashMap::<String, Trace>::new();
+    let mut stack = Vec::<String>::new();
+    let mut trace = BTreeMap::<String, Trace>::new();
+
+    let mut resolve_color = |label: &str, cref: &ColorRef| -> Result<String> {
+        let resolved =
+            match cref {
+                ColorRef::Hex(raw) => Trace {
+                    chain: Vec::new(),
+                    hex: normalize_hex(raw)?,
+                },
+                ColorRef::Path(path) => resolve_path(palette, path, &mut memo, &mut stack)
+                    .map_err(|source| Error::Resolve {
+                        label: label.to_string(),
+                        path: path.clone(),
+                        source: Box::new(source),
+                    })?,
+            };
+        let hex = resolved.hex.clone();
+        trace.insert(label.to_string(), resolved);
+        Ok(hex)
+    };
+
+    let colors_light = resolve_tree(&palette.colors.light, "colors.light", &mut resolve_color)?;
+    let colors_dark = resolve_tree(&palette.colors.dark, "colors.dark", &mut resolve_color)?;
+    let accents = resolve_tree(&palette.accents, "accents", &mut resolve_color)?;
 
-    let mut accents = BTreeMap::new();
-    for (k, v) in &palette.accents {
-        accents.insert(k.clone(), resolve_color(&format!("accents.{k}"), v)?);
+    let mut groups = BTreeMap::new();
+    for (name, tree) in &palette.groups {
+        groups.insert(name.clone(), resolve_tree(tree, name, &mut resolve_color)?);
     }
 
     let resolve_row = |row: &AnsiRow,
@@ -162,6 +619,8 @@ pub fn resolve_palette(palette: &Palette) -> Result<ResolvedPalette> {
         })
     };
 
+    let tokens = resolve_tokens(&palette.tokens, "tokens", &mut resolve_color)?;
+
     let ansi_light_normal = resolve_row(
         &palette.ansi.light.normal,
         "ansi.light.normal",
@@ -192,43 +651,130 @@ pub fn resolve_palette(palette: &Palette) -> Result<ResolvedPalette> {
         accents,
         ansi: ResolvedAnsi {
             light: ResolvedAnsiScheme {
+                extended: extended_table(&ansi_light_normal),
                 normal: ansi_light_normal,
                 bright: ansi_light_bright,
             },
             dark: ResolvedAnsiScheme {
+                extended: extended_table(&ansi_dark_normal),
                 normal: ansi_dark_normal,
                 bright: ansi_dark_bright,
             },
         },
+        tokens,
+        vars: resolve_vars(&palette.vars)?,
+        groups,
+        trace,
     })
 }
 
+fn resolve_tree(
+    tree: &ColorTree,
+    base: &str,
+    f: &mut dyn FnMut(&str, &ColorRef) -> Result<String>,
+) -> Result<ResolvedTree> {
+    let mut out = BTreeMap::new();
+    for (k, node) in tree {
+        let label = format!("{base}.{k}");
+        let resolved = match node {
+            ColorNode::Color(cref) => ResolvedNode::Color(f(&label, cref)?),
+            ColorNode::Scale(scale) => ResolvedNode::Scale(ResolvedScale(
+                scale
+                    .colors
+                    .iter()
+                    .map(|(step, cref)| Ok((step.clone(), f(&format!("{label}.{step}"), cref)?)))
+                    .collect::<Result<_>>()?,
+            )),
+            ColorNode::Group(children) => ResolvedNode::Group(resolve_tree(children, &label, f)?),
+        };
+        out.insert(k.clone(), resolved);
+    }
+    Ok(out)
+}
+
+fn resolve_tokens(
+    nodes: &BTreeMap<String, TokenNode>,
+    base: &str,
+    f: &mut dyn FnMut(&str, &ColorRef) -> Result<String>,
+) -> Result<BTreeMap<String, ResolvedTokenNode>> {
+    let mut out = BTreeMap::new();
+    for (k, node) in nodes {
+        let label = format!("{base}.{k}");
+        let resolved = match node {
+            TokenNode::Token(token) => ResolvedTokenNode::Token(ResolvedToken {
+                light: f(&format!("{label}.light"), &token.light)?,
+                dark: f(&format!("{label}.dark"), &token.dark)?,
+            }),
+            TokenNode::Group(children) => {
+                ResolvedTokenNode::Group(resolve_tokens(children, &label, f)?)
+            }
+        };
+        out.insert(k.clone(), resolved);
+    }
+    Ok(out)
+}
+
+/// Build xterm colors 16–255 from the eight base colors of `row`.
+///
+/// The 6×6×6 cube is a trilinear blend between the base colors placed on the
+/// corners (black at the origin, white opposite), and the 24-step grayscale
+/// ramp runs from black to white. Both are interpolated in OKLab so steps are
+/// perceptually even.
+fn extended_table(row: &ResolvedAnsiRow) -> Vec<String> {
+    let base = row
+        .colors()
+        .map(|hex| Oklab::from_hex(hex).expect("resolved colors are valid hex"));
+    let [black, red, green, yellow, blue, magenta, cyan, white] = base;
+
+    let mut out = Vec::with_capacity(240);
+    for r in 0..6 {
+        let tr = r as f32 / 5.0;
+        for g in 0..6 {
+            let tg = g as f32 / 5.0;
+            for b in 0..6 {
+                let tb = b as f32 / 5.0;
+                let c0 = black.lerp(red, tr).lerp(green.lerp(yellow, tr), tg);
+                let c1 = blue.lerp(magenta, tr).lerp(cyan.lerp(white, tr), tg);
+                out.push(c0.lerp(c1, tb).to_hex());
+            }
+        }
+    }
+    for step in 0..24 {
+        let t = (step + 1) as f32 / 25.0;
+        out.push(black.lerp(white, t).to_hex());
+    }
+    out
+}
+
 fn resolve_path(
     palette: &Palette,
     path: &str,
-    memo: &mut HashMap<String, String>,
+    memo: &mut HashMap<String, Trace>,
     stack: &mut Vec<String>,
-) -> Result<String> {
+) -> Result<Trace> {
     if let Some(val) = memo.get(path) {
         return Ok(val.clone());
     }
 
     if stack.contains(&path.to_string()) {
         let cycle = stack.join(" -> ");
-        bail!("cycle detected: {cycle} -> {path}");
+        return Err(Error::Cycle(format!("{cycle} -> {path}")));
     }
 
-    let cref = lookup_color_ref(palette, path).with_context(|| {
-        format!(
-            "missing path '{}'; expected colors.*, accents.*, or ansi.*.*.*",
-            path
-        )
-    })?;
+    let cref =
+        lookup_color_ref(palette, path).ok_or_else(|| Error::MissingPath(path.to_string()))?;
 
     stack.push(path.to_string());
     let resolved = match cref {
-        ColorRef::Hex(raw) => normalize_hex(raw)?,
-        ColorRef::Path(next) => resolve_path(palette, next, memo, stack)?,
+        ColorRef::Hex(raw) => Trace {
+            chain: vec![path.to_string()],
+            hex: normalize_hex(raw)?,
+        },
+        ColorRef::Path(next) => {
+            let mut next = resolve_path(palette, next, memo, stack)?;
+            next.chain.insert(0, path.to_string());
+            next
+        }
     };
     stack.pop();
 
@@ -240,24 +786,14 @@ fn lookup_color_ref<'a>(palette: &'a Palette, path: &str) -> Option<&'a ColorRef
     let mut parts = path.split('.');
     match parts.next()? {
         "colors" => {
-            let tone = parts.next()?;
-            let key = parts.next()?;
-            if parts.next().is_some() {
-                return None;
-            }
-            match tone {
-                "light" => palette.colors.light.get(key),
-                "dark" => palette.colors.dark.get(key),
-                _ => None,
-            }
-        }
-        "accents" => {
-            let key = parts.next()?;
-            if parts.next().is_some() {
-                return None;
-            }
-            palette.accents.get(key)
+            let tree = match parts.next()? {
+                "light" => &palette.colors.light,
+                "dark" => &palette.colors.dark,
+                _ => return None,
+            };
+            lookup_tree(tree, parts)
         }
+        "accents" => lookup_tree(&palette.accents, parts),
         "ansi" => {
             let tone = parts.next()?;
             let level = parts.next()?;
@@ -287,79 +823,453 @@ fn lookup_color_ref<'a>(palette: &'a Palette, path: &str) -> Option<&'a ColorRef
                 _ => None,
             }
         }
-        _ => None,
+        "tokens" => {
+            let mut nodes = &palette.tokens;
+            loop {
+                match nodes.get(parts.next()?)? {
+                    TokenNode::Group(children) => nodes = children,
+                    TokenNode::Token(token) => {
+                        let tone = parts.next()?;
+                        if parts.next().is_some() {
+                            return None;
+                        }
+                        return match tone {
+                            "light" => Some(&token.light),
+                            "dark" => Some(&token.dark),
+                            _ => None,
+                        };
+                    }
+                }
+            }
+        }
+        group => lookup_tree(palette.groups.get(group)?, parts),
     }
 }
 
+fn lookup_tree<'a, 'p>(
+    mut tree: &'a ColorTree,
+    mut parts: impl Iterator<Item = &'p str>,
+) -> Option<&'a ColorRef> {
+    loop {
+        match tree.get(parts.next()?)? {
+            ColorNode::Group(children) => tree = children,
+            ColorNode::Color(cref) => {
+                return if parts.next().is_none() {
+                    Some(cref)
+                } else {
+                    None
+                };
+            }
+            ColorNode::Scale(scale) => {
+                let step = parts.next()?;
+                if parts.next().is_some() {
+                    return None;
+                }
+                return scale
+                    .colors
+                    .iter()
+                    .find(|(s, _)| s == step)
+                    .map(|(_, cref)| cref);
+            }
+        }
+    }
+}
+
+/// Every color tree with its dotted base path.
+fn color_trees(palette: &Palette) -> Vec<(String, &ColorTree)> {
+    let mut trees = vec![
+        ("colors.light".to_string(), &palette.colors.light),
+        ("colors.dark".to_string(), &palette.colors.dark),
+        ("accents".to_string(), &palette.accents),
+    ];
+    trees.extend(
+        palette
+            .groups
+            .iter()
+            .map(|(name, tree)| (name.clone(), tree)),
+    );
+    trees
+}
+
+/// Generate the step colors of every tonal scale.
+///
+/// A scale's base may be a step of another scale, so scales are expanded in
+/// passes until a pass makes no progress. With `defer`, scales whose base
+/// doesn't resolve yet are left empty instead of failing, for a later call
+/// once derived colors exist.
+fn expand_scales(palette: &mut Palette, defer: bool) -> Result<()> {
+    fn collect(
+        palette: &Palette,
+        tree: &ColorTree,
+        base: &str,
+        memo: &mut HashMap<String, Trace>,
+        out: &mut HashMap<String, Vec<(String, ColorRef)>>,
+        pending: &mut Option<Error>,
+    ) -> Result<()> {
+        for (k, node) in tree {
+            let label = format!("{base}.{k}");
+            match node {
+                ColorNode::Scale(scale) if scale.colors.is_empty() => {
+                    match scale_colors(palette, &label, scale, memo) {
+                        Ok(colors) => {
+                            out.insert(label, colors);
+                        }
+                        Err(err @ Error::Resolve { .. }) => {
+                            pending.get_or_insert(err);
+                        }
+                        Err(err) => return Err(err),
+                    }
+                }
+                ColorNode::Group(children) => {
+                    collect(palette, children, &label, memo, out, pending)?
+                }
+                ColorNode::Scale(_) | ColorNode::Color(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn fill(
+        tree: &mut ColorTree,
+        base: &str,
+        colors: &mut HashMap<String, Vec<(String, ColorRef)>>,
+    ) {
+        for (k, node) in tree {
+            let label = format!("{base}.{k}");
+            match node {
+                ColorNode::Scale(scale) => {
+                    if let Some(expanded) = colors.remove(&label) {
+                        scale.colors = expanded;
+                    }
+                }
+                ColorNode::Group(children) => fill(children, &label, colors),
+                ColorNode::Color(_) => {}
+            }
+        }
+    }
+
+    loop {
+        let mut memo = HashMap::new();
+        let mut colors = HashMap::new();
+        let mut pending = None;
+        for (base, tree) in color_trees(palette) {
+            collect(palette, tree, &base, &mut memo, &mut colors, &mut pending)?;
+        }
+        if colors.is_empty() {
+            return match pending {
+                Some(err) if !defer => Err(err),
+                _ => Ok(()),
+            };
+        }
+
+        fill(&mut palette.colors.light, "colors.light", &mut colors);
+        fill(&mut palette.colors.dark, "colors.dark", &mut colors);
+        fill(&mut palette.accents, "accents", &mut colors);
+        for (name, tree) in &mut palette.groups {
+            fill(tree, name, &mut colors);
+        }
+    }
+}
+
+/// Check a scale's steps and base color, then generate its colors.
+fn scale_colors(
+    palette: &Palette,
+    label: &str,
+    scale: &Scale,
+    memo: &mut HashMap<String, Trace>,
+) -> Result<Vec<(String, ColorRef)>> {
+    let invalid = |message: &str| Error::InvalidScale {
+        label: label.to_string(),
+        message: message.to_string(),
+    };
+    if scale.steps.is_empty() {
+        return Err(invalid("steps must not be empty"));
+    }
+    if scale.steps.windows(2).any(|w| w[0] >= w[1]) || scale.steps.iter().any(|s| *s > 1000) {
+        return Err(invalid("steps must increase from 0 to at most 1000"));
+    }
+
+    let base = match &scale.scale {
+        ColorRef::Hex(raw) => normalize_hex(raw).map_err(|_| Error::InvalidColor {
+            label: label.to_string(),
+            value: raw.clone(),
+        })?,
+        ColorRef::Path(path) => {
+            resolve_path(palette, path, memo, &mut Vec::new())
+                .map_err(|source| Error::Resolve {
+                    label: label.to_string(),
+                    path: path.clone(),
+                    source: Box::new(source),
+                })?
+                .hex
+        }
+    };
+    let hexes = tonal_scale(&base, &scale.steps).expect("base is normalized");
+    Ok(scale
+        .steps
+        .iter()
+        .zip(hexes)
+        .map(|(step, hex)| (step.to_string(), ColorRef::Hex(hex)))
+        .collect())
+}
+
+/// Remove `derive = "<tone>"` from the color variants, returning
+/// `(derived, source)` pairs.
+fn take_derive_modes(palette: &mut Palette) -> Result<Vec<(Tone, Tone)>> {
+    let mut modes = Vec::new();
+    for tone in Tone::ALL {
+        let tree = palette.colors.tree_mut(tone);
+        let Some(ColorNode::Color(cref)) = tree.get("derive") else {
+            continue;
+        };
+        let value = cref.as_str().to_string();
+        match value.parse::<Tone>() {
+            Ok(source) if source != tone => modes.push((tone, source)),
+            _ => return Err(Error::InvalidDerive { tone, value }),
+        }
+        tree.remove("derive");
+    }
+    if let [_, (tone, source)] = modes[..] {
+        return Err(Error::InvalidDerive {
+            tone,
+            value: source.to_string(),
+        });
+    }
+    Ok(modes)
+}
+
+/// Fill each deriving variant's missing keys from its source variant, with
+/// perceptual lightness inverted. Keys the variant sets itself are kept.
+fn derive_colors(palette: &mut Palette, modes: &[(Tone, Tone)]) -> Result<()> {
+    for &(tone, source) in modes {
+        // Scales are shared as written; their steps already run light to dark.
+        let scales = palette.colors.tree(source).clone();
+        copy_missing_scales(&scales, palette.colors.tree_mut(tone));
+
+        let base = format!("colors.{source}");
+        let mut leaves = Vec::new();
+        check_tree(palette.colors.tree(source), &base, &mut |label, cref| {
+            leaves.push((label[base.len() + 1..].to_string(), cref.clone()));
+            Ok(())
+        })?;
+
+        let mut memo = HashMap::new();
+        let mut derived = Vec::new();
+        for (key, cref) in leaves {
+            let hex = match &cref {
+                // A reference within the source variant points to the same
+                // key in the derived one.
+                ColorRef::Path(path) if path.starts_with(&format!("{base}.")) => {
+                    let target = format!("colors.{tone}{}", &path[base.len()..]);
+                    derived.push((key, ColorRef::Path(target)));
+                    continue;
+                }
+                ColorRef::Hex(raw) => normalize_hex(raw)?,
+                ColorRef::Path(path) => {
+                    resolve_path(palette, path, &mut memo, &mut Vec::new())
+                        .map_err(|source| Error::Resolve {
+                            label: format!("{base}.{key}"),
+                            path: path.clone(),
+                            source: Box::new(source),
+                        })?
+                        .hex
+                }
+            };
+            let inverted = invert_lightness(&hex).expect("hex is normalized");
+            derived.push((key, ColorRef::Hex(inverted)));
+        }
+
+        for (key, cref) in derived {
+            if insert_missing(palette.colors.tree_mut(tone), &key, cref) {
+                palette.colors.derived.push(format!("colors.{tone}.{key}"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn copy_missing_scales(source: &ColorTree, target: &mut ColorTree) {
+    for (k, node) in source {
+        match (node, target.get_mut(k)) {
+            (ColorNode::Scale(_), None) => {
+                target.insert(k.clone(), node.clone());
+            }
+            (ColorNode::Group(children), Some(ColorNode::Group(existing))) => {
+                copy_missing_scales(children, existing);
+            }
+            (ColorNode::Group(children), None) => {
+                let mut group = ColorTree::new();
+                copy_missing_scales(children, &mut group);
+                if !group.is_empty() {
+                    target.insert(k.clone(), ColorNode::Group(group));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Insert `cref` at the dotted `key` unless the tree already sets it (or
+/// sets one of its parents to a color). Returns whether it was inserted.
+fn insert_missing(mut tree: &mut ColorTree, key: &str, cref: ColorRef) -> bool {
+    let mut parts = key.split('.').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            if tree.contains_key(part) {
+                return false;
+            }
+            tree.insert(part.to_string(), ColorNode::Color(cref));
+            return true;
+        }
+        match tree
+            .entry(part.to_string())
+            .or_insert_with(|| ColorNode::Group(ColorTree::new()))
+        {
+            ColorNode::Group(children) => tree = children,
+            ColorNode::Color(_) | ColorNode::Scale(_) => return false,
+        }
+    }
+    false
+}
+
 fn normalize_hex(raw: &str) -> Result<String> {
     let re = Regex::new(r"^#[0-9A-Fa-f]{6}$").unwrap();
     if !re.is_match(raw) {
-        bail!("invalid hex color: {raw}");
+        return Err(Error::InvalidHex(raw.to_string()));
     }
     Ok(raw.to_uppercase())
 }
 
-fn validate_palette(palette: &Palette) -> Result<()> {
+pub(crate) fn validate_palette(palette: &Palette) -> Result<()> {
+    if let Some(version) = &palette.meta.version {
+        semver::Version::parse(version).map_err(|source| Error::InvalidVersion {
+            version: version.clone(),
+            source,
+        })?;
+    }
+
     let hex_re = Regex::new(r"^#[0-9A-Fa-f]{6}$").unwrap();
 
     let mut check_ref = |label: &str, cref: &ColorRef| -> Result<()> {
         match cref {
             ColorRef::Hex(s) if hex_re.is_match(s) => Ok(()),
-            ColorRef::Hex(s) => bail!("{label} has invalid hex color: {s}"),
+            ColorRef::Hex(s) => Err(Error::InvalidColor {
+                label: label.to_string(),
+                value: s.clone(),
+            }),
             ColorRef::Path(p) if p.contains('.') => Ok(()),
-            ColorRef::Path(p) => bail!("{label} path must contain at least one '.' segment: {p}"),
+            ColorRef::Path(p) => Err(Error::InvalidPath {
+                label: label.to_string(),
+                path: p.clone(),
+            }),
         }
     };
 
-    for (k, v) in &palette.colors.light {
-        check_ref(&format!("colors.light.{k}"), v)?;
+    for name in palette.groups.keys() {
+        if RESERVED_GROUPS.contains(&name.as_str()) {
+            return Err(Error::ReservedGroup(name.clone()));
+        }
     }
-    for (k, v) in &palette.colors.dark {
-        check_ref(&format!("colors.dark.{k}"), v)?;
+    for (base, tree) in color_trees(palette) {
+        check_dotted_keys(tree, &base)?;
     }
-    for (k, v) in &palette.accents {
-        check_ref(&format!("accents.{k}"), v)?;
+    visit_refs(palette, &mut check_ref)
+}
+
+/// Reject tree keys containing `.`, which would make dotted paths ambiguous.
+fn check_dotted_keys(tree: &ColorTree, base: &str) -> Result<()> {
+    for (k, node) in tree {
+        if k.contains('.') {
+            return Err(Error::DottedKey {
+                base: base.to_string(),
+                key: k.clone(),
+            });
+        }
+        if let ColorNode::Group(children) = node {
+            check_dotted_keys(children, &format!("{base}.{k}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Call `f` with the dotted path and raw value of every color key. Keys
+/// containing `.` have no unambiguous path and are skipped; validation
+/// rejects them.
+fn visit_refs(palette: &Palette, f: &mut dyn FnMut(&str, &ColorRef) -> Result<()>) -> Result<()> {
+    check_tree(&palette.colors.light, "colors.light", f)?;
+    check_tree(&palette.colors.dark, "colors.dark", f)?;
+    check_tree(&palette.accents, "accents", f)?;
+    for (name, tree) in &palette.groups {
+        check_tree(tree, name, f)?;
     }
 
     let check_row = |row: &AnsiRow,
                      base: &str,
                      f: &mut dyn FnMut(&str, &ColorRef) -> Result<()>|
      -> Result<()> {
-        f(&format!("{base}.black"), &row.black)?;
-        f(&format!("{base}.red"), &row.red)?;
-        f(&format!("{base}.green"), &row.green)?;
-        f(&format!("{base}.yellow"), &row.yellow)?;
-        f(&format!("{base}.blue"), &row.blue)?;
-        f(&format!("{base}.magenta"), &row.magenta)?;
-        f(&format!("{base}.cyan"), &row.cyan)?;
-        f(&format!("{base}.white"), &row.white)?;
+        for (name, cref) in row.entries() {
+            f(&format!("{base}.{name}"), cref)?;
+        }
         Ok(())
     };
 
-    check_row(
-        &palette.ansi.light.normal,
-        "ansi.light.normal",
-        &mut check_ref,
-    )?;
-    check_row(
-        &palette.ansi.light.bright,
-        "ansi.light.bright",
-        &mut check_ref,
-    )?;
-    check_row(
-        &palette.ansi.dark.normal,
-        "ansi.dark.normal",
-        &mut check_ref,
-    )?;
-    check_row(
-        &palette.ansi.dark.bright,
-        "ansi.dark.bright",
-        &mut check_ref,
-    )?;
+    check_row(&palette.ansi.light.normal, "ansi.light.normal", f)?;
+    check_row(&palette.ansi.light.bright, "ansi.light.bright", f)?;
+    check_row(&palette.ansi.dark.normal, "ansi.dark.normal", f)?;
+    check_row(&palette.ansi.dark.bright, "ansi.dark.bright", f)?;
+
+    check_tokens(&palette.tokens, "tokens", f)
+}
+
+/// Names already used by the template context.
+const RESERVED_GROUPS: [&str; 2] = ["light", "dark"];
 
+fn check_tree(
+    tree: &ColorTree,
+    base: &str,
+    f: &mut dyn FnMut(&str, &ColorRef) -> Result<()>,
+) -> Result<()> {
+    for (k, node) in tree {
+        if k.contains('.') {
+            continue;
+        }
+        let label = format!("{base}.{k}");
+        match node {
+            ColorNode::Color(cref) => f(&label, cref)?,
+            ColorNode::Scale(scale) => {
+                for (step, cref) in &scale.colors {
+                    f(&format!("{label}.{step}"), cref)?;
+                }
+            }
+            ColorNode::Group(children) => check_tree(children, &label, f)?,
+        }
+    }
     Ok(())
 }
 
+fn check_tokens(
+    nodes: &BTreeMap<String, TokenNode>,
+    base: &str,
+    f: &mut dyn FnMut(&str, &ColorRef) -> Result<()>,
+) -> Result<()> {
+    for (k, node) in nodes {
+        let label = format!("{base}.{k}");
+        match node {
+            TokenNode::Token(token) => {
+                f(&format!("{label}.light"), &token.light)?;
+                f(&format!("{label}.dark"), &token.dark)?;
+            }
+            TokenNode::Group(children) => check_tokens(children, &label, f)?,
+        }
+    }
+    Ok(())
+}
+
+/// A complete palette for tests elsewhere in the crate to tweak with
+/// `.replace()`.
+#[cfg(test)]
+pub(crate) const TEST_PALETTE: &str = include_str!("../tests/fixtures/palette.toml");
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -422,6 +1332,10 @@ blue    = "#6666FF"
 magenta = "#FF66FF"
 cyan    = "#66FFFF"
 white   = "#FFFFFF"
+
+[tokens]
+syntax.comment = { light = "colors.light.secondary", dark = "colors.dark.secondary" }
+diff.added = { light = "ansi.light.normal.green", dark = "#00FF00" }
 "##;
 
     #[test]
@@ -459,21 +1373,305 @@ white   = "#FFFFFF"
         let palette: Palette = toml::from_str(BASE_TOML).unwrap();
         let resolved = resolve_palette(&palette).unwrap();
         assert_eq!(
-            resolved.accents.get("warning").unwrap(),
-            "#111111",
+            resolved.accents["warning"],
+            ResolvedNode::Color("#111111".into()),
             "warning should resolve to colors.light.primary"
         );
         assert_eq!(
-            resolved.colors.light.get("text_primary").unwrap(),
-            "#FFFFFF"
+            resolved.colors.light["text_primary"],
+            ResolvedNode::Color("#FFFFFF".into())
         );
     }
 
+    #[test]
+    fn generates_extended_ansi_table() {
+        let palette: Palette = toml::from_str(BASE_TOML).unwrap();
+        let resolved = resolve_palette(&palette).unwrap();
+        let scheme = &resolved.ansi.light;
+
+        assert_eq!(scheme.extended.len(), 240);
+        assert_eq!(scheme.index(16), Some("#111111"), "cube origin is black");
+        assert_eq!(scheme.index(196), Some("#AA0000"), "cube red corner");
+        assert_eq!(
+            scheme.index(231),
+            Some("#FFFFFF"),
+            "cube far corner is white"
+        );
+        assert_eq!(scheme.index(256), None);
+    }
+
+    #[test]
+    fn resolves_tokens_per_tone() {
+        let palette: Palette = toml::from_str(BASE_TOML).unwrap();
+        let resolved = resolve_palette(&palette).unwrap();
+        let tokens: BTreeMap<_, _> = resolved.token_entries().into_iter().collect();
+
+        assert_eq!(tokens["syntax.comment"].light, "#222222");
+        assert_eq!(tokens["syntax.comment"].dark, "#111111");
+        assert_eq!(tokens["diff.added"].light, "#00AA00");
+        assert_eq!(tokens["diff.added"].dark, "#00FF00");
+    }
+
+    #[test]
+    fn resolves_paths_into_tokens() {
+        let with_ref = BASE_TOML.replace("info = \"#123456\"", "info = \"tokens.diff.added.dark\"");
+        let palette: Palette = toml::from_str(&with_ref).unwrap();
+        let resolved = resolve_palette(&palette).unwrap();
+        assert_eq!(
+            resolved.accents["info"],
+            ResolvedNode::Color("#00FF00".into())
+        );
+    }
+
+    #[test]
+    fn resolves_nested_groups_at_any_depth() {
+        let nested = format!(
+            "{BASE_TOML}\n[ui.sidebar]\nbackground = \"syntax.keyword\"\n\n[syntax]\nkeyword = \"colors.dark.nested.deep\"\n"
+        )
+        .replace(
+            "[colors.dark]\n",
+            "[colors.dark]\nnested.deep = \"#ABCDEF\"\n",
+        );
+        let palette: Palette = toml::from_str(&nested).unwrap();
+        validate_palette(&palette).unwrap();
+        let resolved = resolve_palette(&palette).unwrap();
+
+        let ui = flatten_tree(&resolved.groups["ui"]);
+        assert_eq!(ui, vec![("sidebar.background".to_string(), "#ABCDEF")]);
+        let dark: BTreeMap<_, _> = flatten_tree(&resolved.colors.dark).into_iter().collect();
+        assert_eq!(dark["nested.deep"], "#ABCDEF");
+    }
+
+    #[test]
+    fn rejects_incomplete_ansi_row() {
+        let bad = BASE_TOML.replacen("magenta = \"#AA00AA\"\n", "", 1);
+        let err = toml::from_str::<Palette>(&bad).unwrap_err();
+        assert!(
+            err.to_string().contains("missing field `magenta`"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn rejects_reserved_group_names() {
+        let bad = format!("{BASE_TOML}\n[dark]\nbackground = \"#000000\"\n");
+        let palette: Palette = toml::from_str(&bad).unwrap();
+        let err = validate_palette(&palette).unwrap_err();
+        assert!(
+            err.to_string().contains("reserved"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn rejects_non_semver_version() {
+        let bad = BASE_TOML.replace("version = \"0.1.0\"", "version = \"1.2\"");
+        let palette: Palette = toml::from_str(&bad).unwrap();
+        let err = validate_palette(&palette).unwrap_err();
+        assert!(
+            err.to_string().contains("not a valid semver version"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn derives_slug_and_reverse_dns_id() {
+        let mut meta: Meta = toml::from_str(
+            "name = \"Alpental Night (Blue)\"\nhomepage = \"https://github.com/ascarter/alpental\"",
+        )
+        .unwrap();
+        assert_eq!(meta.slug(), "alpental-night-blue");
+        assert_eq!(
+            meta.reverse_dns_id(),
+            "com.github.ascarter.alpental-night-blue"
+        );
+
+        meta.homepage = None;
+        meta.publisher = Some("ascarter".into());
+        assert_eq!(meta.reverse_dns_id(), "ascarter.alpental-night-blue");
+
+        meta.identifier = Some("com.example.theme".into());
+        assert_eq!(meta.reverse_dns_id(), "com.example.theme");
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let palette = Palette::parse(BASE_TOML).unwrap();
+        let reparsed = Palette::parse(&palette.to_toml().unwrap()).unwrap();
+        let before = crate::export::to_value(&palette.resolve().unwrap(), None).unwrap();
+        let after = crate::export::to_value(&reparsed.resolve().unwrap(), None).unwrap();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn rejects_dotted_keys_and_leaves_them_out_of_entries() {
+        let dotted = BASE_TOML.replace(
+            "info = \"#123456\"",
+            "info = \"#123456\"\n\"side.bar\" = \"#654321\"",
+        );
+        let err = Palette::parse(&dotted).unwrap_err();
+        assert!(
+            matches!(&err, Error::DottedKey { base, key } if base == "accents" && key == "side.bar"),
+            "unexpected error: {err}"
+        );
+
+        let mut palette = Palette::parse(BASE_TOML).unwrap();
+        palette.accents.insert(
+            "side.bar".into(),
+            ColorNode::Color(ColorRef::Hex("#654321".into())),
+        );
+        let entries = palette.entries();
+        assert!(entries.iter().any(|(key, _)| key == "accents.info"));
+        assert!(!entries.iter().any(|(key, _)| key.contains("side")));
+    }
+
+    #[test]
+    fn records_resolution_chains() {
+        let chained = BASE_TOML.replace("info = \"#123456\"", "info = \"ansi.light.normal.black\"");
+        let resolved = Palette::parse(&chained).unwrap().resolve().unwrap();
+
+        let info = &resolved.trace["accents.info"];
+        assert_eq!(
+            info.chain,
+            vec!["ansi.light.normal.black", "colors.light.primary"]
+        );
+        assert_eq!(info.hex, "#111111");
+        assert!(resolved.trace["colors.light.primary"].chain.is_empty());
+
+        assert_eq!(
+            resolved.dependents("colors.light.primary"),
+            vec!["accents.info", "accents.warning", "ansi.light.normal.black"]
+        );
+    }
+
+    #[test]
+    fn derives_missing_keys_from_other_variant() {
+        let derived = BASE_TOML.replace(
+            "[colors.dark]\nprimary = \"#000000\"\nsecondary = \"#111111\"\n",
+            "[colors.dark]\nderive = \"light\"\nlink = \"#ABCDEF\"\n",
+        );
+        let derived = derived.replace(
+            "text_primary = \"#ffffff\"",
+            "text_primary = \"#ffffff\"\nlink = \"colors.light.primary\"\nui.panel = \"colors.light.secondary\"",
+        );
+        let palette = Palette::parse(&derived).unwrap();
+        assert_eq!(
+            palette.colors.derived,
+            [
+                "colors.dark.primary",
+                "colors.dark.secondary",
+                "colors.dark.ui.panel"
+            ]
+        );
+        let resolved = palette.resolve().unwrap();
+        let hex = |key: &str| resolved.trace[key].hex.as_str();
+        // Lightness is mirrored: near-black becomes near-white.
+        assert_eq!(hex("colors.dark.primary"), "#C5C5C5");
+        // References within the light variant point into the dark one.
+        let panel = &resolved.trace["colors.dark.ui.panel"];
+        assert_eq!(panel.chain, ["colors.dark.secondary"]);
+        assert_eq!(panel.hex, "#ADADAD");
+        // Keys set explicitly win over derived ones.
+        assert_eq!(hex("colors.dark.link"), "#ABCDEF");
+        assert_eq!(hex("colors.dark.text_primary"), "#EEEEEE");
+
+        let both = derived.replace("[colors.light]\n", "[colors.light]\nderive = \"dark\"\n");
+        let err = Palette::parse(&both).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidDerive {
+                tone: Tone::Dark,
+                ..
+            }
+        ));
+        let own = derived.replace("derive = \"light\"", "derive = \"dark\"");
+        assert!(matches!(
+            Palette::parse(&own).unwrap_err(),
+            Error::InvalidDerive { .. }
+        ));
+    }
+
+    #[test]
+    fn expands_tonal_scales() {
+        let scaled = BASE_TOML.replace(
+            "info = \"#123456\"",
+            "info = \"colors.light.blue.500\"\nbrand = { scale = \"accents.warning\", steps = [100, 900] }",
+        );
+        let scaled = scaled.replace(
+            "text_primary = \"#ffffff\"",
+            "text_primary = \"#ffffff\"\nblue = { scale = \"#2E73FF\", steps = [50, 500, 950] }",
+        );
+        let palette = Palette::parse(&scaled).unwrap();
+        assert!(palette.generated_keys().contains("colors.light.blue.950"));
+
+        let resolved = palette.resolve().unwrap();
+        let ResolvedNode::Scale(blue) = &resolved.colors.light["blue"] else {
+            panic!("{:?}", resolved.colors.light["blue"]);
+        };
+        let steps: Vec<_> = blue.0.iter().map(|(step, _)| step.as_str()).collect();
+        assert_eq!(steps, ["50", "500", "950"]);
+        let lightness: Vec<_> = blue
+            .0
+            .iter()
+            .map(|(_, hex)| Oklab::from_hex(hex).unwrap().l)
+            .collect();
+        assert!(lightness.windows(2).all(|w| w[0] > w[1]), "{lightness:?}");
+        assert_eq!(resolved.trace["accents.info"].hex, blue.0[1].1);
+        // Scales keep their step order when serialized.
+        let json = serde_json::to_string(&resolved.colors.light["blue"]).unwrap();
+        assert!(json.starts_with("{\"50\":"), "{json}");
+        assert!(resolved.trace.contains_key("accents.brand.900"));
+
+        // Bases can be steps of other scales, in any order, and derived keys.
+        let chained = scaled
+            .replace(
+                "blue = { scale",
+                "deep = { scale = \"accents.brand.900\", steps = [500] }\nblue = { scale",
+            )
+            .replace(
+                "[colors.dark]\nprimary = \"#000000\"\nsecondary = \"#111111\"\n",
+                "[colors.dark]\nderive = \"light\"\nsky = { scale = \"colors.dark.blue.500\", steps = [300] }\n\
+                 mirror = { scale = \"colors.dark.primary\", steps = [500] }\n",
+            );
+        let palette = Palette::parse(&chained).unwrap();
+        let resolved = palette.resolve().unwrap();
+        let hex = |key: &str| resolved.trace[key].hex.clone();
+        assert_eq!(
+            hex("colors.light.deep.500"),
+            tonal_scale(&hex("accents.brand.900"), &[500]).unwrap()[0]
+        );
+        assert_eq!(
+            hex("colors.dark.sky.300"),
+            tonal_scale(&hex("colors.light.blue.500"), &[300]).unwrap()[0]
+        );
+        assert_eq!(
+            hex("colors.dark.mirror.500"),
+            tonal_scale(&hex("colors.dark.primary"), &[500]).unwrap()[0]
+        );
+        assert!(
+            palette
+                .colors
+                .derived
+                .contains(&"colors.dark.primary".to_string())
+        );
+
+        let missing = scaled.replace("scale = \"accents.warning\"", "scale = \"accents.nope\"");
+        let err = Palette::parse(&missing).unwrap_err();
+        assert!(
+            matches!(&err, Error::Resolve { label, path, .. } if label == "accents.brand" && path == "accents.nope"),
+            "{err}"
+        );
+
+        let bad = scaled.replace("[50, 500, 950]", "[500, 50]");
+        let err = Palette::parse(&bad).unwrap_err();
+        assert!(matches!(err, Error::InvalidScale { label, .. } if label == "colors.light.blue"));
+    }
+
     #[test]
     fn detects_missing_path() {
         let bad = BASE_TOML.replace("colors.light.primary", "colors.light.missing");
         let palette: Palette = toml::from_str(&bad).unwrap();
-        let err = resolve_palette(&palette).unwrap_err();
+        let err = anyhow::Error::from(resolve_palette(&palette).unwrap_err());
         assert!(
             format!("{err:#}").contains("missing path"),
             "unexpected error: {err:#}"
@@ -489,7 +1687,7 @@ white   = "#FFFFFF"
             )
             .replace("info = \"#123456\"", "info = \"accents.warning\"");
         let palette: Palette = toml::from_str(&bad).unwrap();
-        let err = resolve_palette(&palette).unwrap_err();
+        let err = anyhow::Error::from(resolve_palette(&palette).unwrap_err());
         assert!(
             format!("{err:#}").contains("cycle detected"),
             "unexpected error: {err:#}"
@@ -0,0 +1,453 @@
+//! Sample content painted in truecolor with a resolved palette, to judge how
+//! a theme reads in use rather than as swatches.
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use anyhow::{Result, bail};
+use veneer_theme::{ResolvedPalette, color::hex_to_rgb, palette::Tone};
+
+/// Sample content `show --preview` can render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scene {
+    /// A shell session: `git status` and `cargo test`.
+    Terminal,
+    /// A syntax-highlighted Rust function.
+    Code,
+    /// A `git diff`.
+    Diff,
+    /// A colored `ls -l` listing.
+    Ls,
+}
+
+impl Scene {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scene::Terminal => "terminal",
+            Scene::Code => "code",
+            Scene::Diff => "diff",
+            Scene::Ls => "ls",
+        }
+    }
+}
+
+impl fmt::Display for Scene {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scene {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "terminal" => Ok(Scene::Terminal),
+            "code" => Ok(Scene::Code),
+            "diff" => Ok(Scene::Diff),
+            "ls" => Ok(Scene::Ls),
+            other => Err(format!(
+                "unknown preview '{other}'; expected terminal, code, diff or ls"
+            )),
+        }
+    }
+}
+
+/// A part of a scene painted in one color, e.g. keywords in `code`.
+struct Role {
+    name: &'static str,
+    /// Token used when the palette defines it, e.g. `tokens.syntax.keyword`.
+    token: &'static str,
+    /// ANSI color (0–15) used otherwise; `None` is the foreground.
+    ansi: Option<usize>,
+}
+
+const fn role(name: &'static str, token: &'static str, ansi: Option<usize>) -> Role {
+    Role { name, token, ansi }
+}
+
+/// Roles of the `code`, `diff` and `ls` scenes. The terminal scene is plain
+/// ANSI, as a shell would print it.
+const ROLES: [Role; 17] = [
+    role("keyword", "tokens.syntax.keyword", Some(5)),
+    role("function", "tokens.syntax.function", Some(4)),
+    role("type", "tokens.syntax.type", Some(3)),
+    role("string", "tokens.syntax.string", Some(2)),
+    role("number", "tokens.syntax.number", Some(6)),
+    role("macro", "tokens.syntax.macro", Some(6)),
+    role("comment", "tokens.syntax.comment", Some(8)),
+    role("header", "tokens.diff.header", None),
+    role("hunk", "tokens.diff.hunk", Some(6)),
+    role("added", "tokens.diff.added", Some(2)),
+    role("removed", "tokens.diff.removed", Some(1)),
+    role("directory", "tokens.ls.directory", Some(4)),
+    role("executable", "tokens.ls.executable", Some(2)),
+    role("symlink", "tokens.ls.symlink", Some(6)),
+    role("archive", "tokens.ls.archive", Some(1)),
+    role("image", "tokens.ls.image", Some(5)),
+    role("hidden", "tokens.ls.hidden", Some(8)),
+];
+
+/// Names of the roles `--map` can assign, in scene order.
+pub fn role_names() -> impl Iterator<Item = &'static str> {
+    ROLES.iter().map(|role| role.name)
+}
+
+/// Colors a scene is painted with, for one tone.
+pub struct SceneColors<'a> {
+    pub background: &'a str,
+    pub foreground: &'a str,
+    ansi: [&'a str; 16],
+    roles: BTreeMap<&'static str, &'a str>,
+}
+
+impl<'a> SceneColors<'a> {
+    /// `colors.<tone>.background` and `.text`, falling back to the ANSI
+    /// row's black and white when the palette doesn't define them.
+    ///
+    /// `mappings` assign roles to palette keys (`keyword` →
+    /// `tokens.syntax.keyword`). A key resolves to `<key>.<tone>` when that
+    /// exists, as for tokens, and `{tone}` in a key is replaced by the tone.
+    pub fn new(
+        palette: &'a ResolvedPalette,
+        tone: Tone,
+        mappings: &BTreeMap<String, String>,
+    ) -> Result<Self> {
+        let scheme = match tone {
+            Tone::Light => &palette.ansi.light,
+            Tone::Dark => &palette.ansi.dark,
+        };
+        let lookup = |key: &str| {
+            let key = key.replace("{tone}", tone.as_str());
+            palette
+                .trace
+                .get(&format!("{key}.{tone}"))
+                .or_else(|| palette.trace.get(&key))
+                .map(|trace| trace.hex.as_str())
+        };
+        let background = lookup("colors.{tone}.background").unwrap_or(&scheme.normal.black);
+        let foreground = lookup("colors.{tone}.text").unwrap_or(&scheme.normal.white);
+        let ansi: [&str; 16] = std::array::from_fn(|idx| scheme.index(idx).unwrap_or(foreground));
+
+        for name in mappings.keys() {
+            if !role_names().any(|role| role == name) {
+                bail!(
+                    "unknown preview role '{name}'; expected one of {}",
+                    role_names().collect::<Vec<_>>().join(", ")
+                );
+            }
+        }
+        let mut roles = BTreeMap::new();
+        for role in &ROLES {
+            let hex = match mappings.get(role.name) {
+                Some(key) => match lookup(key) {
+                    Some(hex) => hex,
+                    None => bail!("preview role '{}' maps to unknown key '{key}'", role.name),
+                },
+                None => lookup(role.token)
+                    .unwrap_or_else(|| role.ansi.map_or(foreground, |idx| ansi[idx])),
+            };
+            roles.insert(role.name, hex);
+        }
+
+        Ok(Self {
+            background,
+            foreground,
+            ansi,
+            roles,
+        })
+    }
+
+    fn ansi(&self, idx: usize) -> Option<&'a str> {
+        Some(self.ansi[idx])
+    }
+
+    fn role(&self, name: &str) -> Option<&'a str> {
+        self.roles.get(name).copied()
+    }
+}
+
+/// A run of text in one color; `None` is the scene's foreground.
+type Span<'a> = (Option<&'a str>, &'a str);
+
+/// Paint `scene` as lines `width` columns wide.
+pub fn render(scene: Scene, colors: &SceneColors, width: usize) -> Vec<String> {
+    let lines = match scene {
+        Scene::Terminal => terminal(colors),
+        Scene::Code => code(colors),
+        Scene::Diff => diff(colors),
+        Scene::Ls => ls(colors),
+    };
+    paint(colors, &lines, width)
+}
+
+fn terminal<'a>(colors: &SceneColors<'a>) -> Vec<Vec<Span<'a>>> {
+    let [red, green, yellow, blue, magenta] = [1, 2, 3, 4, 5].map(|i| colors.ansi(i));
+    let dim = colors.ansi(8);
+    let prompt = |command| {
+        vec![
+            (blue, "~/src/veneer"),
+            (None, " on "),
+            (magenta, "main"),
+            (None, " $ "),
+            (None, command),
+        ]
+    };
+
+    vec![
+        prompt("git status --short"),
+        vec![(green, "M "), (None, " src/preview.rs")],
+        vec![(red, " M"), (None, " veneer.toml")],
+        vec![(red, "??"), (None, " notes.md")],
+        prompt("cargo test"),
+        vec![(yellow, "warning"), (None, ": unused import `Path`")],
+        vec![(None, "test palette::parses ... "), (green, "ok")],
+        vec![(None, "test render::strict ... "), (red, "FAILED")],
+        vec![(dim, "2 passed; 1 failed; finished in 0.25s")],
+    ]
+}
+
+fn code<'a>(colors: &SceneColors<'a>) -> Vec<Vec<Span<'a>>> {
+    let [keyword, function, ty, string, number, mac, comment] = [
+        "keyword", "function", "type", "string", "number", "macro", "comment",
+    ]
+    .map(|role| colors.role(role));
+
+    vec![
+        vec![(comment, "// Greet everyone once.")],
+        vec![
+            (keyword, "fn "),
+            (function, "greet"),
+            (None, "(names: &["),
+            (ty, "String"),
+            (None, "]) -> "),
+            (ty, "usize"),
+            (None, " {"),
+        ],
+        vec![
+            (keyword, "    for "),
+            (None, "name "),
+            (keyword, "in "),
+            (None, "names {"),
+        ],
+        vec![
+            (mac, "        println!"),
+            (None, "("),
+            (string, "\"Hello, {name}!\""),
+            (None, ");"),
+        ],
+        vec![(None, "    }")],
+        vec![
+            (None, "    names."),
+            (function, "len"),
+            (None, "() + "),
+            (number, "1"),
+        ],
+        vec![(None, "}")],
+    ]
+}
+
+fn diff<'a>(colors: &SceneColors<'a>) -> Vec<Vec<Span<'a>>> {
+    let [header, hunk, added, removed] =
+        ["header", "hunk", "added", "removed"].map(|role| colors.role(role));
+
+    vec![
+        vec![(header, "diff --git a/veneer.toml b/veneer.toml")],
+        vec![(header, "--- a/veneer.toml")],
+        vec![(header, "+++ b/veneer.toml")],
+        vec![(hunk, "@@ -14,4 +14,4 @@"), (None, " [colors.light]")],
+        vec![(None, " background = \"#FFFFFF\"")],
+        vec![(removed, "-primary    = \"#2E73FF\"")],
+        vec![(added, "+primary    = \"#3B82F6\"")],
+        vec![(None, " text       = \"#111111\"")],
+    ]
+}
+
+fn ls<'a>(colors: &SceneColors<'a>) -> Vec<Vec<Span<'a>>> {
+    let [directory, executable, symlink, archive, image, hidden] = [
+        "directory",
+        "executable",
+        "symlink",
+        "archive",
+        "image",
+        "hidden",
+    ]
+    .map(|role| colors.role(role));
+
+    vec![
+        vec![(None, "drwxr-xr-x  "), (directory, "src")],
+        vec![(None, "drwxr-xr-x  "), (directory, "themes")],
+        vec![(None, "-rw-r--r--  "), (hidden, ".gitignore")],
+        vec![(None, "-rw-r--r--  Cargo.toml")],
+        vec![(None, "-rwxr-xr-x  "), (executable, "build.sh")],
+        vec![
+            (None, "lrwxrwxrwx  "),
+            (symlink, "latest"),
+            (None, " -> themes/v0.1.0"),
+        ],
+        vec![(None, "-rw-r--r--  "), (archive, "themes.tar.gz")],
+        vec![(None, "-rw-r--r--  "), (image, "screenshot.png")],
+    ]
+}
+
+/// Render lines of spans on the scene background, padded to `width`.
+fn paint(colors: &SceneColors, lines: &[Vec<Span>], width: usize) -> Vec<String> {
+    let bg = background_escape(colors.background);
+    lines
+        .iter()
+        .map(|spans| {
+            let mut out = format!("{bg} ");
+            let mut len = 1;
+            for (fg, text) in spans {
+                out.push_str(&foreground_escape(fg.unwrap_or(colors.foreground)));
+                out.push_str(text);
+                len += text.chars().count();
+            }
+            out.push_str(&" ".repeat(width.saturating_sub(len)));
+            out.push_str("\u{1b}[0m");
+            out
+        })
+        .collect()
+}
+
+fn foreground_escape(hex: &str) -> String {
+    hex_to_rgb(hex).map_or_else(String::new, |(r, g, b)| format!("\u{1b}[38;2;{r};{g};{b}m"))
+}
+
+fn background_escape(hex: &str) -> String {
+    hex_to_rgb(hex).map_or_else(String::new, |(r, g, b)| format!("\u{1b}[48;2;{r};{g};{b}m"))
+}
+
+#[cfg(test)]
+mod tests {
+    use veneer_theme::Palette;
+
+    use super::*;
+    use crate::TEST_PALETTE;
+
+    fn resolve(raw: &str) -> ResolvedPalette {
+        Palette::parse(raw).unwrap().resolve().unwrap()
+    }
+
+    fn mappings(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(role, key)| (role.to_string(), key.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_scenes() {
+        for scene in [Scene::Terminal, Scene::Code, Scene::Diff, Scene::Ls] {
+            assert_eq!(scene.as_str().parse::<Scene>(), Ok(scene));
+        }
+        assert_eq!(
+            "Code".parse::<Scene>(),
+            Err("unknown preview 'Code'; expected terminal, code, diff or ls".into())
+        );
+    }
+
+    #[test]
+    fn uses_tokens_then_ansi_colors() {
+        let raw = format!(
+            "{TEST_PALETTE}\n[tokens]\nsyntax.keyword = {{ light = \"#AA0000\", dark = \"colors.dark.primary\" }}\n"
+        );
+        let palette = resolve(&raw);
+
+        let light = SceneColors::new(&palette, Tone::Light, &BTreeMap::new()).unwrap();
+        assert_eq!(light.background, "#FFFFFF");
+        assert_eq!(light.foreground, "#111111");
+        assert_eq!(light.role("keyword"), Some("#AA0000"));
+
+        let dark = SceneColors::new(&palette, Tone::Dark, &BTreeMap::new()).unwrap();
+        assert_eq!(dark.role("keyword"), Some("#2E73FF"));
+        // No tokens: the role's ANSI color, or the foreground.
+        assert_eq!(dark.role("function"), Some("#458588"));
+        assert_eq!(dark.role("comment"), Some("#3C3836"));
+        assert_eq!(dark.role("header"), Some("#E6EDF3"));
+        assert_eq!(dark.ansi(15), Some("#F9F5D7"));
+    }
+
+    #[test]
+    fn falls_back_to_ansi_black_and_white() {
+        let raw = TEST_PALETTE
+            .replace("background = \"#0E1117\"\n", "")
+            .replace("text = \"#E6EDF3\"\n", "")
+            .replace("\"colors.dark.background\"", "\"#101010\"")
+            .replace("\"colors.dark.text\"", "\"#F0F0F0\"");
+        let palette = resolve(&raw);
+        let dark = SceneColors::new(&palette, Tone::Dark, &BTreeMap::new()).unwrap();
+        assert_eq!(dark.background, "#101010");
+        assert_eq!(dark.foreground, "#F0F0F0");
+        assert_eq!(dark.role("header"), Some("#F0F0F0"));
+    }
+
+    #[test]
+    fn maps_roles_to_keys() {
+        let raw = format!(
+            "{TEST_PALETTE}\n[tokens]\nsyntax.comment = {{ light = \"#AA0000\", dark = \"#00AA00\" }}\n"
+        );
+        let palette = resolve(&raw);
+        let map = mappings(&[
+            ("keyword", "colors.{tone}.background"),
+            ("string", "tokens.syntax.comment"),
+            ("added", "accents.info"),
+        ]);
+
+        let light = SceneColors::new(&palette, Tone::Light, &map).unwrap();
+        assert_eq!(light.role("keyword"), Some("#FFFFFF"));
+        assert_eq!(light.role("string"), Some("#AA0000"));
+        assert_eq!(light.role("added"), Some("#3FA7D6"));
+        let dark = SceneColors::new(&palette, Tone::Dark, &map).unwrap();
+        assert_eq!(dark.role("keyword"), Some("#0E1117"));
+        assert_eq!(dark.role("string"), Some("#00AA00"));
+        assert_eq!(dark.role("comment"), Some("#00AA00"));
+
+        let err = SceneColors::new(&palette, Tone::Dark, &mappings(&[("keywords", "x")]))
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string()
+                .starts_with("unknown preview role 'keywords'; expected one of keyword, function,"),
+            "{err}"
+        );
+        let err = SceneColors::new(
+            &palette,
+            Tone::Dark,
+            &mappings(&[("keyword", "colors.{tone}.primray")]),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "preview role 'keyword' maps to unknown key 'colors.{tone}.primray'"
+        );
+    }
+
+    #[test]
+    fn pads_lines_to_the_width() {
+        let palette = resolve(TEST_PALETTE);
+        let colors = SceneColors::new(&palette, Tone::Dark, &BTreeMap::new()).unwrap();
+        let bg = "\u{1b}[48;2;14;17;23m";
+        let fg = "\u{1b}[38;2;230;237;243m";
+        let red = "\u{1b}[38;2;204;36;29m";
+
+        let lines = [vec![(None, "ab"), (Some("#CC241D"), "cd")]];
+        assert_eq!(
+            paint(&colors, &lines, 10),
+            [format!("{bg} {fg}ab{red}cd     \u{1b}[0m")]
+        );
+        // Longer lines aren't cut or padded.
+        assert_eq!(
+            paint(&colors, &lines, 3),
+            [format!("{bg} {fg}ab{red}cd\u{1b}[0m")]
+        );
+
+        for line in render(Scene::Diff, &colors, 50) {
+            let visible: String = line
+                .split('\u{1b}')
+                .map(|part| part.split_once('m').map_or(part, |(_, text)| text))
+                .collect();
+            assert_eq!(visible.chars().count(), 50, "{visible:?}");
+        }
+    }
+}
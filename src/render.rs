@@ -1,33 +1,154 @@
 use std::{
-    fs,
+    fmt, fs,
     path::{Path, PathBuf},
 };
 
-use anyhow::{Context, Result};
 use serde_json::Value;
 use tera::{Context as TeraContext, Tera};
 use walkdir::WalkDir;
 
-use crate::palette::{ResolvedPalette, load_palette, resolve_palette};
+use crate::{
+    Error, Result,
+    color::{Oklab, hex_to_rgb},
+    lint,
+    palette::{Palette, ResolvedAnsi, ResolvedAnsiScheme, ResolvedPalette, Tone},
+    template::{self, ArgValue},
+};
 
-pub fn build(palette_path: &PathBuf, src: &PathBuf, dest: Option<&PathBuf>) -> Result<()> {
-    let ctx = {
-        let palette = load_palette(palette_path)?;
-        let resolved = resolve_palette(&palette)?;
-        build_context(&resolved)?
-    };
+/// Renders Tera templates against a resolved palette, with veneer's helpers registered.
+#[derive(Clone)]
+pub struct Renderer {
+    tera: Tera,
+    context: TeraContext,
+}
+
+impl Renderer {
+    pub fn new(resolved: &ResolvedPalette) -> Result<Self> {
+        let mut tera = Tera::default();
+        tera.autoescape_on(vec![]);
+        register_helpers(&mut tera, resolved);
+        Ok(Self {
+            tera,
+            context: build_context(resolved)?,
+        })
+    }
+
+    /// Template context built from the palette (`meta`, `light`, `dark`, ...).
+    pub fn context(&self) -> &TeraContext {
+        &self.context
+    }
+
+    /// Mutable context, for adding values of your own.
+    pub fn context_mut(&mut self) -> &mut TeraContext {
+        &mut self.context
+    }
+
+    pub fn register_function<F: tera::Function + 'static>(&mut self, name: &str, function: F) {
+        self.tera.register_function(name, function);
+    }
+
+    pub fn register_filter<F: tera::Filter + 'static>(&mut self, name: &str, filter: F) {
+        self.tera.register_filter(name, filter);
+    }
+
+    /// Render template source; `name` is used in error messages.
+    pub fn render_str(&self, name: &str, source: &str) -> Result<String> {
+        let mut tera = self.tera.clone();
+        tera.add_raw_template(name, source)
+            .map_err(|source| Error::TemplateParse {
+                name: name.to_string(),
+                source,
+            })?;
+        tera.render(name, &self.context)
+            .map_err(|source| Error::TemplateRender {
+                name: name.to_string(),
+                source,
+            })
+    }
+
+    /// Problems found without rendering: reads the context doesn't define
+    /// (which `default` filters would otherwise hide) and color helpers given
+    /// values that aren't hex colors.
+    pub fn strict_issues(&self, name: &str, source: &str) -> Result<Vec<StrictIssue>> {
+        let analysis = template::analyze(name, source)?;
+        let context = self.context.clone().into_json();
+        let mut issues = Vec::new();
+
+        for read in &analysis.occurrences {
+            if lint::lookup(&context, &read.path).is_none() {
+                issues.push(StrictIssue {
+                    line: read.line,
+                    message: format!("undefined variable '{}'", read.path),
+                });
+            }
+        }
+
+        for arg in &analysis.color_args {
+            let helper = &arg.helper;
+            let (line, message) = match &arg.value {
+                ArgValue::Literal(s) if hex_to_rgb(s).is_none() => (
+                    arg.line,
+                    format!("{helper} expects a hex color, got \"{s}\""),
+                ),
+                ArgValue::Path { path, .. } => match lint::lookup(&context, path) {
+                    Some(Value::String(s)) if hex_to_rgb(s).is_some() => continue,
+                    // Already reported as undefined.
+                    None => continue,
+                    Some(value) => (
+                        arg.line,
+                        format!("{helper} expects a hex color, but '{path}' is {value}"),
+                    ),
+                },
+                _ => continue,
+            };
+            issues.push(StrictIssue { line, message });
+        }
+
+        issues.sort_by_key(|issue| issue.line);
+        Ok(issues)
+    }
+
+    pub fn render_file(&self, template_path: &Path) -> Result<String> {
+        let template = fs::read_to_string(template_path).map_err(|source| Error::Read {
+            path: template_path.to_path_buf(),
+            source,
+        })?;
+        self.render_str(&template_path.display().to_string(), &template)
+    }
+}
+
+/// A problem found by [`Renderer::strict_issues`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrictIssue {
+    /// 1-based line, when the offending text could be found in the source.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for StrictIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {line}: {}", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+pub fn build(palette_path: &Path, src: &Path, dest: Option<&Path>) -> Result<()> {
+    let resolved = Palette::load(palette_path)?.resolve()?;
+    let renderer = Renderer::new(&resolved)?;
 
     let src_kind = detect_source_kind(src)?;
     let (base, templates) = collect_templates(&src_kind)?;
 
     if templates.is_empty() {
-        anyhow::bail!("no templates matched {}", src.display());
+        return Err(Error::NoTemplates(src.to_path_buf()));
     }
 
     match src_kind {
         SourceKind::SingleFile { path } => {
             let out_path = determine_out_path(&path, dest)?;
-            render_one(&path, &ctx, &out_path)
+            render_one(&renderer, &path, &out_path)
         }
         _ => {
             let dest_mode = resolve_dest_mode(dest)?;
@@ -41,33 +162,28 @@ pub fn build(palette_path: &PathBuf, src: &PathBuf, dest: Option<&PathBuf>) -> R
                         PathBuf::from(combined)
                     }
                 };
-                render_one(&path, &ctx, &out_path)?;
+                render_one(&renderer, &path, &out_path)?;
             }
             Ok(())
         }
     }
 }
 
-pub fn check_single(palette_path: &PathBuf, template_path: &PathBuf) -> Result<()> {
-    let palette = load_palette(palette_path)?;
-    let resolved = resolve_palette(&palette)?;
-    let ctx = build_context(&resolved)?;
-
-    let template = fs::read_to_string(template_path)
-        .with_context(|| format!("reading {}", template_path.display()))?;
-
-    let mut tera = Tera::default();
-    tera.add_raw_template("inline", &template)
-        .with_context(|| format!("registering template {}", template_path.display()))?;
-    tera.autoescape_on(vec![]);
-    register_helpers(&mut tera);
-
-    tera.render("inline", &ctx)
-        .with_context(|| format!("rendering template {}", template_path.display()))?;
+pub fn check_single(palette_path: &Path, template_path: &Path) -> Result<()> {
+    let resolved = Palette::load(palette_path)?.resolve()?;
+    Renderer::new(&resolved)?.render_file(template_path)?;
     Ok(())
 }
 
-fn determine_out_path(template_path: &Path, dest: Option<&PathBuf>) -> Result<PathBuf> {
+/// Template files selected by `src`: a single file, every `*.tera` under a
+/// directory, or the matches of a glob.
+pub fn template_files(src: &Path) -> Result<Vec<PathBuf>> {
+    let kind = detect_source_kind(src)?;
+    let (_, paths) = collect_templates(&kind)?;
+    Ok(paths)
+}
+
+fn determine_out_path(template_path: &Path, dest: Option<&Path>) -> Result<PathBuf> {
     // Base filename: template filename with .tera removed.
     let file_name = template_path
         .file_name()
@@ -79,11 +195,11 @@ fn determine_out_path(template_path: &Path, dest: Option<&PathBuf>) -> Result<Pa
             if path.is_dir() {
                 path.join(file_name)
             } else {
-                path.clone()
+                path.to_path_buf()
             }
         }
         None => std::env::current_dir()
-            .context("reading current directory")?
+            .map_err(Error::CurrentDir)?
             .join(file_name),
     };
 
@@ -100,41 +216,60 @@ fn strip_tera_extension(os: &std::ffi::OsStr) -> std::ffi::OsString {
 
 fn build_context(resolved: &ResolvedPalette) -> Result<TeraContext> {
     let mut ctx = TeraContext::new();
-    ctx.try_insert("meta", &resolved.meta)?;
-    ctx.try_insert("light", &resolved.colors.light)?;
-    ctx.try_insert("dark", &resolved.colors.dark)?;
-    ctx.try_insert("accents", &resolved.accents)?;
-    ctx.try_insert("ansi", &resolved.ansi)?;
+    let mut meta = tera::to_value(&resolved.meta).map_err(|err| Error::Context(err.into()))?;
+    meta["slug"] = Value::from(resolved.meta.slug());
+    meta["id"] = Value::from(resolved.meta.reverse_dns_id());
+    ctx.insert("meta", &meta);
+    ctx.try_insert("light", &resolved.colors.light)
+        .map_err(Error::Context)?;
+    ctx.try_insert("dark", &resolved.colors.dark)
+        .map_err(Error::Context)?;
+    ctx.try_insert("accents", &resolved.accents)
+        .map_err(Error::Context)?;
+    ctx.try_insert("ansi", &resolved.ansi)
+        .map_err(Error::Context)?;
+    ctx.try_insert("tokens", &resolved.tokens)
+        .map_err(Error::Context)?;
+    ctx.try_insert("vars", &resolved.vars)
+        .map_err(Error::Context)?;
+    ctx.try_insert("colors", &resolved.colors)
+        .map_err(Error::Context)?;
+    for (name, tree) in &resolved.groups {
+        ctx.try_insert(name.as_str(), tree)
+            .map_err(Error::Context)?;
+    }
     Ok(ctx)
 }
 
-fn register_helpers(tera: &mut Tera) {
+fn register_helpers(tera: &mut Tera, resolved: &ResolvedPalette) {
     tera.register_function("with_alpha", with_alpha);
     tera.register_function("rgba", rgba);
     tera.register_function("hsla", hsla);
     tera.register_function("rgba_floats", rgba_floats);
     tera.register_filter("lowercase", lowercase_filter);
-}
 
-fn render_one(template_path: &Path, ctx: &TeraContext, out_path: &Path) -> Result<()> {
-    let template = fs::read_to_string(template_path)
-        .with_context(|| format!("reading {}", template_path.display()))?;
-
-    let mut tera = Tera::default();
-    tera.add_raw_template("inline", &template)
-        .with_context(|| format!("registering template {}", template_path.display()))?;
-    tera.autoescape_on(vec![]);
-    register_helpers(&mut tera);
+    let ansi = resolved.ansi.clone();
+    tera.register_filter(
+        "nearest_ansi256",
+        move |value: &Value, args: &std::collections::HashMap<String, Value>| {
+            nearest_ansi256_filter(&ansi, value, args)
+        },
+    );
+}
 
-    let rendered = tera
-        .render("inline", ctx)
-        .with_context(|| format!("rendering template {}", template_path.display()))?;
+fn render_one(renderer: &Renderer, template_path: &Path, out_path: &Path) -> Result<()> {
+    let rendered = renderer.render_file(template_path)?;
 
     if let Some(parent) = out_path.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("creating directory {}", parent.display()))?;
+        fs::create_dir_all(parent).map_err(|source| Error::CreateDir {
+            path: parent.to_path_buf(),
+            source,
+        })?;
     }
-    fs::write(out_path, rendered).with_context(|| format!("writing {}", out_path.display()))?;
+    fs::write(out_path, rendered).map_err(|source| Error::Write {
+        path: out_path.to_path_buf(),
+        source,
+    })?;
     Ok(())
 }
 
@@ -189,6 +324,43 @@ fn lowercase_filter(
     }
 }
 
+fn nearest_ansi256_filter(
+    ansi: &ResolvedAnsi,
+    value: &Value,
+    args: &std::collections::HashMap<String, Value>,
+) -> tera::Result<Value> {
+    let color = match value {
+        Value::String(s) => s,
+        other => {
+            return Err(tera::Error::msg(format!(
+                "nearest_ansi256 filter expects a string, got {other:?}"
+            )));
+        }
+    };
+    let tone: Tone = expect_string(args, "tone")?
+        .parse()
+        .map_err(|err: Error| tera::Error::msg(err.to_string()))?;
+    let scheme = match tone {
+        Tone::Light => &ansi.light,
+        Tone::Dark => &ansi.dark,
+    };
+    let idx = nearest_ansi256(scheme, color)
+        .ok_or_else(|| tera::Error::msg(format!("invalid hex color: {color}")))?;
+    Ok(Value::from(idx))
+}
+
+/// Index (0–255) of the scheme color perceptually closest to `hex`.
+fn nearest_ansi256(scheme: &ResolvedAnsiScheme, hex: &str) -> Option<usize> {
+    let target = Oklab::from_hex(hex)?;
+    (0..256)
+        .filter_map(|idx| {
+            let candidate = Oklab::from_hex(scheme.index(idx)?)?;
+            Some((idx, target.distance(candidate)))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(idx, _)| idx)
+}
+
 fn expect_string(
     args: &std::collections::HashMap<String, Value>,
     key: &str,
@@ -223,16 +395,6 @@ fn with_alpha_hex(hex: &str, alpha: f32) -> tera::Result<String> {
     Ok(format!("#{r:02X}{g:02X}{b:02X}{a:02X}"))
 }
 
-fn hex_to_rgb(hex: &str) -> Option<(u8, u8, u8)> {
-    if hex.len() != 7 || !hex.starts_with('#') {
-        return None;
-    }
-    let r = u8::from_str_radix(&hex[1..3], 16).ok()?;
-    let g = u8::from_str_radix(&hex[3..5], 16).ok()?;
-    let b = u8::from_str_radix(&hex[5..7], 16).ok()?;
-    Some((r, g, b))
-}
-
 fn rgb_to_hsl(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
     let r = r as f32 / 255.0;
     let g = g as f32 / 255.0;
@@ -271,7 +433,7 @@ enum SourceKind {
     Glob { pattern: String, base: PathBuf },
 }
 
-fn detect_source_kind(src: &PathBuf) -> Result<SourceKind> {
+fn detect_source_kind(src: &Path) -> Result<SourceKind> {
     let src_str = src.to_string_lossy();
     if has_glob_chars(&src_str) {
         let base = glob_base(&src_str);
@@ -282,10 +444,14 @@ fn detect_source_kind(src: &PathBuf) -> Result<SourceKind> {
     }
 
     if src.is_dir() {
-        return Ok(SourceKind::Directory { root: src.clone() });
+        return Ok(SourceKind::Directory {
+            root: src.to_path_buf(),
+        });
     }
 
-    Ok(SourceKind::SingleFile { path: src.clone() })
+    Ok(SourceKind::SingleFile {
+        path: src.to_path_buf(),
+    })
 }
 
 fn has_glob_chars(s: &str) -> bool {
@@ -293,9 +459,7 @@ fn has_glob_chars(s: &str) -> bool {
 }
 
 fn glob_base(pattern: &str) -> PathBuf {
-    let idx = pattern
-        .find(|c| matches!(c, '*' | '?' | '[' | '{'))
-        .unwrap_or(pattern.len());
+    let idx = pattern.find(['*', '?', '[', '{']).unwrap_or(pattern.len());
     let before = &pattern[..idx];
     let base = match before.rfind(std::path::MAIN_SEPARATOR) {
         Some(pos) => &before[..=pos],
@@ -350,16 +514,16 @@ enum DestMode {
     Prefix(PathBuf),
 }
 
-fn resolve_dest_mode(dest: Option<&PathBuf>) -> Result<DestMode> {
+fn resolve_dest_mode(dest: Option<&Path>) -> Result<DestMode> {
     let sep = std::path::MAIN_SEPARATOR;
     let mode = match dest {
-        None => DestMode::Directory(std::env::current_dir().context("reading current directory")?),
+        None => DestMode::Directory(std::env::current_dir().map_err(Error::CurrentDir)?),
         Some(path) => {
             let s = path.to_string_lossy();
             if path.is_dir() || s.ends_with(sep) {
-                DestMode::Directory(path.clone())
+                DestMode::Directory(path.to_path_buf())
             } else {
-                DestMode::Prefix(path.clone())
+                DestMode::Prefix(path.to_path_buf())
             }
         }
     };
@@ -435,6 +599,87 @@ white="#111111"
         assert_eq!(out, Value::String("emerald mix".into()));
     }
 
+    #[test]
+    fn nearest_ansi256_picks_closest_index() {
+        let palette = Palette::parse(MINIMAL_PALETTE).unwrap();
+        let resolved = palette.resolve().unwrap();
+
+        assert_eq!(nearest_ansi256(&resolved.ansi.dark, "#010101"), Some(0));
+        assert_eq!(nearest_ansi256(&resolved.ansi.dark, "#101010"), Some(8));
+        assert_eq!(nearest_ansi256(&resolved.ansi.dark, "nope"), None);
+    }
+
+    #[test]
+    fn renderer_accepts_custom_helpers() {
+        let resolved = Palette::parse(MINIMAL_PALETTE).unwrap().resolve().unwrap();
+        let mut renderer = Renderer::new(&resolved).unwrap();
+        renderer.register_filter(
+            "shout",
+            |value: &Value, _: &std::collections::HashMap<String, Value>| {
+                Ok(Value::String(format!(
+                    "{}!",
+                    value.as_str().unwrap_or_default()
+                )))
+            },
+        );
+
+        let out = renderer
+            .render_str("inline", "{{ meta.name | shout }} {{ accents.primary }}")
+            .unwrap();
+        assert_eq!(out, "Test! #111111");
+
+        let err = renderer.render_str("broken", "{{ nope }}").unwrap_err();
+        assert!(matches!(err, Error::TemplateRender { ref name, .. } if name == "broken"));
+    }
+
+    #[test]
+    fn strict_mode_reports_lines_for_every_issue() {
+        let resolved = Palette::parse(MINIMAL_PALETTE).unwrap().resolve().unwrap();
+        let renderer = Renderer::new(&resolved).unwrap();
+        let source = "{{ accents.primary }}\n\
+                      {{ accents.primray | default(value=\"\") }}\n\
+                      {{ rgba(color=meta.name, alpha=0.5) }} {{ \"red\" | nearest_ansi256(tone=\"dark\") }}\n\
+                      accents.primray {% for k, v in accents %}{{ with_alpha(color=v, alpha=0.5) }}{% endfor %}\n\
+                      {{ with_alpha(color='blue', alpha=0.5) }} {{ accents.primray }}\n";
+
+        let issues: Vec<String> = renderer
+            .strict_issues("inline", source)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                "line 2: undefined variable 'accents.primray'",
+                "line 3: rgba expects a hex color, but 'meta.name' is \"Test\"",
+                "line 3: nearest_ansi256 expects a hex color, got \"red\"",
+                "line 5: undefined variable 'accents.primray'",
+                "line 5: with_alpha expects a hex color, got \"blue\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn iterates_scales_in_step_order() {
+        let palette = MINIMAL_PALETTE.replace(
+            "primary = \"#111111\"",
+            "primary = \"#111111\"\nblue = { scale = \"#2E73FF\", steps = [50, 100, 900] }",
+        );
+        let resolved = Palette::parse(&palette).unwrap().resolve().unwrap();
+        let renderer = Renderer::new(&resolved).unwrap();
+        let out = renderer
+            .render_str(
+                "scale",
+                "{% for step, hex in accents.blue %}{{ step }} {% endfor %}{{ accents.blue.100 }}",
+            )
+            .unwrap();
+        assert_eq!(
+            out,
+            format!("50 100 900 {}", resolved.trace["accents.blue.100"].hex)
+        );
+    }
+
     #[test]
     fn strips_tera_extension_for_default_output() {
         let path = Path::new("templates/vscode/themes/theme.json.tera");
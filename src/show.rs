@@ -1,17 +1,92 @@
-use std::path::PathBuf;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 
-use crate::palette::{ResolvedAnsiRow, ResolvedPalette, load_palette, resolve_palette};
+use veneer_theme::{
+    Palette,
+    color::hex_to_rgb,
+    diff::{Change, PaletteDiff},
+    extract::Swatch,
+    graph::RefGraph,
+    palette::{
+        ANSI_NAMES, ResolvedAnsiRow, ResolvedPalette, ResolvedTree, Tone, Trace, flatten_tree,
+    },
+};
 
-pub fn run(palette_path: &PathBuf) -> Result<()> {
-    let palette = load_palette(palette_path)?;
-    let resolved = resolve_palette(&palette)?;
-    print_palette(palette_path, &resolved);
+use crate::preview::{self, Scene, SceneColors};
+
+/// Width of each side of a `--preview` scene.
+const PREVIEW_WIDTH: usize = 44;
+
+pub fn run(palette_path: &PathBuf, trace: bool) -> Result<()> {
+    let resolved = Palette::load(palette_path)?.resolve()?;
+    print_palette(palette_path, &resolved, trace);
     Ok(())
 }
 
-fn print_palette(palette_path: &PathBuf, palette: &ResolvedPalette) {
+/// Print sample scenes painted with each tone, light and dark side by side.
+pub fn preview(
+    palette_path: &Path,
+    scenes: &[Scene],
+    mappings: &BTreeMap<String, String>,
+) -> Result<()> {
+    let resolved = Palette::load(palette_path)?.resolve()?;
+    let light = SceneColors::new(&resolved, Tone::Light, mappings)?;
+    let dark = SceneColors::new(&resolved, Tone::Dark, mappings)?;
+
+    println!(
+        "Palette: {} ({})\n",
+        resolved.meta.name,
+        palette_path.display()
+    );
+    for scene in scenes {
+        println!(
+            "{:<PREVIEW_WIDTH$}  Dark ({scene})",
+            format!("Light ({scene})")
+        );
+        let left = preview::render(*scene, &light, PREVIEW_WIDTH);
+        let right = preview::render(*scene, &dark, PREVIEW_WIDTH);
+        for (left, right) in left.iter().zip(&right) {
+            println!("{left}  {right}");
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// Print the resolution chain for `path` and every key that depends on it.
+pub fn why(palette_path: &PathBuf, path: &str) -> Result<()> {
+    let palette = Palette::load(palette_path)?;
+    let resolved = palette.resolve()?;
+    let Some(trace) = resolved.trace.get(path) else {
+        bail!("unknown palette key '{path}'");
+    };
+
+    println!("{}", chain_text(path, trace));
+    let graph = RefGraph::new(&palette);
+    let dependents = graph.dependents(path);
+    if dependents.is_empty() {
+        println!("\nNo other keys depend on {path}.");
+        return Ok(());
+    }
+    println!("\nUsed by:");
+    for key in dependents {
+        println!("  {}", chain_text(key, &resolved.trace[key]));
+    }
+    Ok(())
+}
+
+fn chain_text(key: &str, trace: &Trace) -> String {
+    let mut parts = vec![key];
+    parts.extend(trace.chain.iter().map(String::as_str));
+    parts.push(&trace.hex);
+    parts.join(" → ")
+}
+
+pub fn print_palette(palette_path: &Path, palette: &ResolvedPalette, trace: bool) {
     println!(
         "Palette: {} ({})",
         palette.meta.name,
@@ -23,151 +98,229 @@ fn print_palette(palette_path: &PathBuf, palette: &ResolvedPalette) {
     println!();
 
     let label_width = max_label_width(palette);
+    let section = |title: &str, items: Vec<Item>| {
+        print_section(title, items, label_width, trace.then_some(palette));
+    };
 
-    print_section(
+    section(
         "Colors (Light)",
-        palette
-            .colors
-            .light
-            .iter()
-            .map(|(k, v)| (k.clone(), v.clone()))
-            .collect(),
-        label_width,
+        tree_items(&palette.colors.light, "colors.light"),
     );
-    print_section(
+    section(
         "Colors (Dark)",
-        palette
-            .colors
-            .dark
-            .iter()
-            .map(|(k, v)| (k.clone(), v.clone()))
-            .collect(),
-        label_width,
-    );
-    print_section(
-        "Accents",
-        palette
-            .accents
-            .iter()
-            .map(|(k, v)| (k.clone(), v.clone()))
-            .collect(),
-        label_width,
+        tree_items(&palette.colors.dark, "colors.dark"),
     );
+    section("Accents", tree_items(&palette.accents, "accents"));
+    for (name, tree) in &palette.groups {
+        section(&group_title(name), tree_items(tree, name));
+    }
 
-    print_section(
+    let tokens = palette.token_entries();
+    for (title, tone) in [("Tokens (Light)", "light"), ("Tokens (Dark)", "dark")] {
+        section(
+            title,
+            tokens
+                .iter()
+                .map(|(k, t)| {
+                    let hex = if tone == "light" { &t.light } else { &t.dark };
+                    Item {
+                        label: k.clone(),
+                        path: format!("tokens.{k}.{tone}"),
+                        hex: hex.clone(),
+                    }
+                })
+                .collect(),
+        );
+    }
+
+    section(
         "ANSI (Light / Normal)",
-        ansi_row_items(&palette.ansi.light.normal),
-        label_width,
+        ansi_row_items(&palette.ansi.light.normal, "ansi.light.normal"),
     );
-    print_section(
+    section(
         "ANSI (Light / Bright)",
-        ansi_row_items(&palette.ansi.light.bright),
-        label_width,
+        ansi_row_items(&palette.ansi.light.bright, "ansi.light.bright"),
     );
-    print_section(
+    section(
         "ANSI (Dark / Normal)",
-        ansi_row_items(&palette.ansi.dark.normal),
-        label_width,
+        ansi_row_items(&palette.ansi.dark.normal, "ansi.dark.normal"),
     );
-    print_section(
+    section(
         "ANSI (Dark / Bright)",
-        ansi_row_items(&palette.ansi.dark.bright),
-        label_width,
+        ansi_row_items(&palette.ansi.dark.bright, "ansi.dark.bright"),
     );
 }
 
+/// Print image swatches with their share of the image.
+pub fn print_swatches(title: &str, swatches: &[Swatch]) {
+    let items = swatches
+        .iter()
+        .map(|swatch| Item {
+            label: format!("{:.0}%", swatch.weight * 100.0),
+            path: String::new(),
+            hex: swatch.hex.clone(),
+        })
+        .collect();
+    print_section(title, items, 8, None);
+}
+
+/// Print added, removed and changed keys with swatches, then contrast
+/// regressions.
+pub fn print_diff(old_label: &str, new_label: &str, diff: &PaletteDiff) {
+    println!("--- {old_label}\n+++ {new_label}\n");
+    if diff.is_empty() {
+        println!("No color changes.");
+        return;
+    }
+
+    let width = diff
+        .changes
+        .iter()
+        .map(|change| change.key().len())
+        .max()
+        .unwrap_or(0);
+    for (title, kind) in [
+        ("Added", "added"),
+        ("Removed", "removed"),
+        ("Changed", "changed"),
+    ] {
+        let rows: Vec<_> = diff.changes.iter().filter(|c| c.kind() == kind).collect();
+        if rows.is_empty() {
+            continue;
+        }
+        println!("{title} ({})", rows.len());
+        for change in rows {
+            print!("  {:<width$}  ", change.key());
+            match change {
+                Change::Added { hex, .. } | Change::Removed { hex, .. } => {
+                    println!("{} {hex}", swatch(hex))
+                }
+                Change::Changed {
+                    old, new, delta_e, ..
+                } => println!(
+                    "{} {old} → {} {new}  ΔE {delta_e:.1}",
+                    swatch(old),
+                    swatch(new)
+                ),
+            }
+        }
+        println!();
+    }
+
+    if !diff.contrast.is_empty() {
+        println!("Contrast warnings ({})", diff.contrast.len());
+        for warning in &diff.contrast {
+            println!(
+                "  {} on {}: {:.1}:1 → {:.1}:1, below {}:1",
+                warning.key, warning.background, warning.old, warning.new, warning.level
+            );
+        }
+        println!();
+    }
+}
+
+/// One row of a `show` section.
+struct Item {
+    label: String,
+    /// Full dotted path, used to look up the resolution trace.
+    path: String,
+    hex: String,
+}
+
 fn max_label_width(palette: &ResolvedPalette) -> usize {
     let mut max_len = 0;
 
-    for key in palette.colors.light.keys() {
-        max_len = max_len.max(key.len());
+    let trees = [
+        &palette.colors.light,
+        &palette.colors.dark,
+        &palette.accents,
+    ];
+    for tree in trees.into_iter().chain(palette.groups.values()) {
+        for (key, _) in flatten_tree(tree) {
+            max_len = max_len.max(key.len());
+        }
     }
-    for key in palette.colors.dark.keys() {
+    for (key, _) in palette.token_entries() {
         max_len = max_len.max(key.len());
     }
-    for key in palette.accents.keys() {
-        max_len = max_len.max(key.len());
-    }
-    for key in [
-        "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
-    ] {
+    for key in ANSI_NAMES {
         max_len = max_len.max(key.len());
     }
 
     max_len.max(8)
 }
 
-fn print_section(title: &str, items: Vec<(String, String)>, label_width: usize) {
+fn print_section(
+    title: &str,
+    items: Vec<Item>,
+    label_width: usize,
+    trace: Option<&ResolvedPalette>,
+) {
     if items.is_empty() {
         return;
     }
 
     println!("{title}");
     println!(
-        "{:<width$}  {:<6}  {}",
+        "{:<width$}  {:<6}  hex",
         "key",
         "swatch",
-        "hex",
-        width = label_width
-    );
-    println!(
-        "{:-<width$}  {:-<6}  {}",
-        "",
-        "",
-        "----",
         width = label_width
     );
+    println!("{:-<width$}  {:-<6}  ----", "", "", width = label_width);
 
-    for (label, hex) in items {
-        print!("{:<width$}  ", label, width = label_width);
-        let sw = swatch(&hex);
+    for item in items {
+        print!("{:<width$}  ", item.label, width = label_width);
+        let sw = swatch(&item.hex);
         print!("{sw}");
-        println!("  {hex}");
+        match trace.and_then(|p| p.trace.get(&item.path)) {
+            Some(t) if !t.chain.is_empty() => {
+                let chain = t.chain.join(" → ");
+                println!("  {chain} → {}", item.hex);
+            }
+            _ => println!("  {}", item.hex),
+        }
     }
     println!();
 }
 
-fn ansi_row_items(row: &ResolvedAnsiRow) -> Vec<(String, String)> {
-    vec![
-        ("black".to_string(), row.black.clone()),
-        ("red".to_string(), row.red.clone()),
-        ("green".to_string(), row.green.clone()),
-        ("yellow".to_string(), row.yellow.clone()),
-        ("blue".to_string(), row.blue.clone()),
-        ("magenta".to_string(), row.magenta.clone()),
-        ("cyan".to_string(), row.cyan.clone()),
-        ("white".to_string(), row.white.clone()),
-    ]
+fn tree_items(tree: &ResolvedTree, base: &str) -> Vec<Item> {
+    flatten_tree(tree)
+        .into_iter()
+        .map(|(k, v)| Item {
+            path: format!("{base}.{k}"),
+            label: k,
+            hex: v.to_string(),
+        })
+        .collect()
 }
 
-fn swatch(hex: &str) -> String {
-    if let Some((r, g, b)) = hex_to_rgb(hex) {
-        let luminance = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32) / 255.0;
-        let text = if luminance < 0.5 { 255 } else { 0 };
-        return format!("\u{1b}[48;2;{r};{g};{b}m\u{1b}[38;2;{text};{text};{text}m      \u{1b}[0m");
+/// Section title for a custom top-level group, e.g. `ui` -> `Ui`.
+fn group_title(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
     }
-    hex.to_string()
 }
 
-fn hex_to_rgb(hex: &str) -> Option<(u8, u8, u8)> {
-    if hex.len() != 7 || !hex.starts_with('#') {
-        return None;
-    }
-    let r = u8::from_str_radix(&hex[1..3], 16).ok()?;
-    let g = u8::from_str_radix(&hex[3..5], 16).ok()?;
-    let b = u8::from_str_radix(&hex[5..7], 16).ok()?;
-    Some((r, g, b))
+fn ansi_row_items(row: &ResolvedAnsiRow, base: &str) -> Vec<Item> {
+    ANSI_NAMES
+        .iter()
+        .zip(row.colors())
+        .map(|(name, hex)| Item {
+            label: name.to_string(),
+            path: format!("{base}.{name}"),
+            hex: hex.to_string(),
+        })
+        .collect()
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn parses_hex_to_rgb() {
-        assert_eq!(hex_to_rgb("#A1B2C3"), Some((0xA1, 0xB2, 0xC3)));
-        assert_eq!(hex_to_rgb("#000000"), Some((0, 0, 0)));
-        assert_eq!(hex_to_rgb("123456"), None);
-        assert_eq!(hex_to_rgb("#ffff"), None);
+pub fn swatch(hex: &str) -> String {
+    if let Some((r, g, b)) = hex_to_rgb(hex) {
+        let luminance = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32) / 255.0;
+        let text = if luminance < 0.5 { 255 } else { 0 };
+        return format!("\u{1b}[48;2;{r};{g};{b}m\u{1b}[38;2;{text};{text};{text}m      \u{1b}[0m");
     }
+    hex.to_string()
 }
@@ -0,0 +1,527 @@
+//! Static analysis of Tera templates: which context values a template reads.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use tera::{
+    Template,
+    ast::{Expr, ExprVal, FunctionCall, Node},
+};
+
+use crate::{Error, Result, render};
+
+/// A template file and the context paths it reads.
+#[derive(Debug, Clone)]
+pub struct TemplateReads {
+    pub path: PathBuf,
+    pub reads: BTreeSet<String>,
+}
+
+/// Parse every template selected by `src` (a file, a directory of `*.tera`
+/// or a glob) and collect what each reads.
+pub fn scan(src: &Path) -> Result<Vec<TemplateReads>> {
+    render::template_files(src)?
+        .into_iter()
+        .map(|path| {
+            let source = fs::read_to_string(&path).map_err(|source| Error::Read {
+                path: path.clone(),
+                source,
+            })?;
+            let reads = reads(&path.display().to_string(), &source)?;
+            Ok(TemplateReads { path, reads })
+        })
+        .collect()
+}
+
+/// Helper functions whose `color` argument must be a hex color.
+pub const COLOR_FUNCTIONS: [&str; 4] = ["with_alpha", "rgba", "hsla", "rgba_floats"];
+
+/// Filters whose input must be a hex color.
+pub const COLOR_FILTERS: [&str; 1] = ["nearest_ansi256"];
+
+/// What a template reads and passes to color helpers.
+#[derive(Debug, Clone, Default)]
+pub struct Analysis {
+    /// See [`reads`].
+    pub reads: BTreeSet<String>,
+    /// Every place a context path is read, so repeated reads are each
+    /// reported.
+    pub occurrences: Vec<Occurrence>,
+    pub color_args: Vec<ColorArg>,
+}
+
+/// One read of a context path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Occurrence {
+    pub path: String,
+    /// 1-based line, when the read could be found in the source.
+    pub line: Option<usize>,
+}
+
+/// The color passed to a helper from [`COLOR_FUNCTIONS`] or [`COLOR_FILTERS`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorArg {
+    pub helper: String,
+    pub value: ArgValue,
+    /// 1-based line, when the argument could be found in the source.
+    pub line: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgValue {
+    Literal(String),
+    /// A context path, with its spelling in the source.
+    Path {
+        path: String,
+        raw: String,
+    },
+    /// Locals, filtered values and other expressions known only at render time.
+    Dynamic,
+}
+
+/// Parse a template and collect its reads and color helper arguments.
+pub fn analyze(name: &str, source: &str) -> Result<Analysis> {
+    let template = Template::new(name, None, source).map_err(|source| Error::TemplateParse {
+        name: name.to_string(),
+        source,
+    })?;
+    let mut walker = Walker::default();
+    walker.nodes(&template.ast);
+
+    // Tera's AST has no positions, so the n-th read of a spelling is placed
+    // at its n-th appearance inside a tag.
+    let mut analysis = walker.analysis;
+    let mut locator = Locator::new(source);
+    analysis.occurrences = walker
+        .spellings
+        .into_iter()
+        .map(|(path, spelling)| Occurrence {
+            line: locator.next(&[spelling]),
+            path,
+        })
+        .collect();
+    let mut locator = Locator::new(source);
+    for arg in &mut analysis.color_args {
+        arg.line = match &arg.value {
+            // Tera strings can be quoted three ways.
+            ArgValue::Literal(s) => {
+                locator.next(&[format!("\"{s}\""), format!("'{s}'"), format!("`{s}`")])
+            }
+            ArgValue::Path { raw, .. } => locator.next(std::slice::from_ref(raw)),
+            ArgValue::Dynamic => None,
+        };
+    }
+    Ok(analysis)
+}
+
+/// Context paths a template reads, normalized to dotted form
+/// (`ansi["dark"].normal` becomes `ansi.dark.normal`).
+///
+/// Loop variables, `set` targets and macro arguments are local and not
+/// reported. A subscript that is not a literal (`light[name]`) cuts the path
+/// short, so the whole table before it counts as read.
+pub fn reads(name: &str, source: &str) -> Result<BTreeSet<String>> {
+    Ok(analyze(name, source)?.reads)
+}
+
+/// Finds successive appearances of text inside a template's `{{ }}` and
+/// `{% %}` tags.
+struct Locator<'a> {
+    source: &'a str,
+    tags: Vec<Range<usize>>,
+    /// Where to resume searching, per needle.
+    cursors: BTreeMap<String, usize>,
+}
+
+impl<'a> Locator<'a> {
+    fn new(source: &'a str) -> Self {
+        let mut tags = Vec::new();
+        let mut rest = 0;
+        while let Some(open) = source[rest..].find('{') {
+            let start = rest + open;
+            let close = match source[start + 1..].chars().next() {
+                Some('{') => "}}",
+                Some('%') => "%}",
+                Some('#') => "#}",
+                _ => {
+                    rest = start + 1;
+                    continue;
+                }
+            };
+            let end = source[start + 2..]
+                .find(close)
+                .map_or(source.len(), |idx| start + 2 + idx);
+            if close != "#}" {
+                tags.push(start + 2..end);
+            }
+            rest = (end + 2).min(source.len());
+        }
+        Self {
+            source,
+            tags,
+            cursors: BTreeMap::new(),
+        }
+    }
+
+    /// 1-based line of the next appearance of any of `needles` that is not
+    /// part of a longer path. Later calls with the same first needle
+    /// continue after it.
+    fn next(&mut self, needles: &[String]) -> Option<usize> {
+        let cursor = self.cursors.get(&needles[0]).copied().unwrap_or(0);
+        let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+        let (pos, len) = self
+            .tags
+            .iter()
+            .filter(|tag| tag.end > cursor)
+            .find_map(|tag| {
+                let start = tag.start.max(cursor);
+                let text = &self.source[start..tag.end];
+                needles
+                    .iter()
+                    .flat_map(|needle| {
+                        text.match_indices(needle.as_str())
+                            .filter(|(at, _)| {
+                                let before = text[..*at].chars().next_back();
+                                let after = text[at + needle.len()..].chars().next();
+                                !before.is_some_and(|c| is_ident(c) || c == '.')
+                                    && !after.is_some_and(|c| is_ident(c) || c == '.' || c == '[')
+                            })
+                            .map(|(at, _)| (start + at, needle.len()))
+                    })
+                    .min()
+            })?;
+        self.cursors.insert(needles[0].clone(), pos + len);
+        Some(self.source[..pos].matches('\n').count() + 1)
+    }
+}
+
+/// Palette key prefix behind a context path, following the names the
+/// renderer exposes: `light.primary` reads `colors.light.primary`, and
+/// `ansi.dark.extended` is derived from the whole `ansi.dark` scheme.
+/// `meta` and `vars` hold no colors and map to `None`.
+pub fn palette_key(read: &str) -> Option<String> {
+    let (root, rest) = match read.split_once('.') {
+        Some((root, rest)) => (root, Some(rest)),
+        None => (read, None),
+    };
+    let key = match (root, rest) {
+        ("meta" | "vars", _) => return None,
+        ("light" | "dark", Some(rest)) => format!("colors.{root}.{rest}"),
+        ("light" | "dark", None) => format!("colors.{root}"),
+        ("ansi", Some(rest)) => {
+            let mut parts = rest.splitn(3, '.');
+            match (parts.next(), parts.next()) {
+                (Some(tone), Some("extended")) => format!("ansi.{tone}"),
+                _ => read.to_string(),
+            }
+        }
+        _ => read.to_string(),
+    };
+    Some(key)
+}
+
+/// Whether reading context path `read` reads palette key `key`, either
+/// directly or as part of a whole table.
+pub fn reads_key(read: &str, key: &str) -> bool {
+    let Some(prefix) = palette_key(read) else {
+        return false;
+    };
+    key == prefix
+        || key
+            .strip_prefix(prefix.as_str())
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+#[derive(Default)]
+struct Walker {
+    analysis: Analysis,
+    /// Each non-local read with its spelling (`dark["primary"]`), in walk
+    /// order.
+    spellings: Vec<(String, String)>,
+    locals: Vec<String>,
+}
+
+impl Walker {
+    fn nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::VariableBlock(_, expr) => self.expr(expr),
+            Node::Set(_, set) => {
+                self.expr(&set.value);
+                self.locals.push(set.key.clone());
+            }
+            Node::FilterSection(_, section, _) => {
+                self.call(&section.filter);
+                self.nodes(&section.body);
+            }
+            Node::Block(_, block, _) => self.nodes(&block.body),
+            Node::Forloop(_, forloop, _) => {
+                self.expr(&forloop.container);
+                let depth = self.locals.len();
+                self.locals.extend(forloop.key.iter().cloned());
+                self.locals.push(forloop.value.clone());
+                self.locals.push("loop".to_string());
+                self.nodes(&forloop.body);
+                self.locals.truncate(depth);
+                if let Some(body) = &forloop.empty_body {
+                    self.nodes(body);
+                }
+            }
+            Node::If(cond, _) => {
+                for (_, expr, body) in &cond.conditions {
+                    self.expr(expr);
+                    self.nodes(body);
+                }
+                if let Some((_, body)) = &cond.otherwise {
+                    self.nodes(body);
+                }
+            }
+            Node::MacroDefinition(_, def, _) => {
+                let depth = self.locals.len();
+                for (arg, default) in &def.args {
+                    if let Some(default) = default {
+                        self.expr(default);
+                    }
+                    self.locals.push(arg.clone());
+                }
+                self.nodes(&def.body);
+                self.locals.truncate(depth);
+            }
+            _ => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        self.value(&expr.val);
+        if let Some(first) = expr.filters.first() {
+            if COLOR_FILTERS.contains(&first.name.as_str()) {
+                let value = self.arg_value(&Expr::new(expr.val.clone()));
+                self.analysis.color_args.push(ColorArg {
+                    helper: first.name.clone(),
+                    value,
+                    line: None,
+                });
+            }
+        }
+        for filter in &expr.filters {
+            self.call(filter);
+        }
+    }
+
+    fn call(&mut self, call: &FunctionCall) {
+        // Picks from the ANSI tables without naming them.
+        if call.name == "nearest_ansi256" {
+            self.analysis.reads.insert("ansi".to_string());
+        }
+        if COLOR_FUNCTIONS.contains(&call.name.as_str()) {
+            if let Some(color) = call.args.get("color") {
+                let value = self.arg_value(color);
+                self.analysis.color_args.push(ColorArg {
+                    helper: call.name.clone(),
+                    value,
+                    line: None,
+                });
+            }
+        }
+        for arg in call.args.values() {
+            self.expr(arg);
+        }
+    }
+
+    fn arg_value(&self, expr: &Expr) -> ArgValue {
+        if !expr.filters.is_empty() {
+            return ArgValue::Dynamic;
+        }
+        match &expr.val {
+            ExprVal::String(s) => ArgValue::Literal(s.clone()),
+            ExprVal::Ident(raw) => match normalize_ident(raw) {
+                (path, nested) if nested.is_empty() && !self.is_local(&path) => ArgValue::Path {
+                    path,
+                    raw: raw.clone(),
+                },
+                _ => ArgValue::Dynamic,
+            },
+            _ => ArgValue::Dynamic,
+        }
+    }
+
+    fn is_local(&self, path: &str) -> bool {
+        let root = path.split('.').next().unwrap_or_default();
+        self.locals.iter().any(|local| local == root)
+    }
+
+    fn value(&mut self, val: &ExprVal) {
+        match val {
+            ExprVal::Ident(ident) => self.ident(ident),
+            ExprVal::Math(math) => {
+                self.expr(&math.lhs);
+                self.expr(&math.rhs);
+            }
+            ExprVal::Logic(logic) => {
+                self.expr(&logic.lhs);
+                self.expr(&logic.rhs);
+            }
+            ExprVal::Test(test) => {
+                // `is defined` probes a path without reading it.
+                if !matches!(test.name.as_str(), "defined" | "undefined") {
+                    self.ident(&test.ident);
+                }
+                for arg in &test.args {
+                    self.expr(arg);
+                }
+            }
+            ExprVal::MacroCall(call) => {
+                for arg in call.args.values() {
+                    self.expr(arg);
+                }
+            }
+            ExprVal::FunctionCall(call) => self.call(call),
+            ExprVal::Array(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            ExprVal::StringConcat(concat) => {
+                for value in &concat.values {
+                    self.value(value);
+                }
+            }
+            ExprVal::In(test) => {
+                self.expr(&test.lhs);
+                self.expr(&test.rhs);
+            }
+            ExprVal::String(_) | ExprVal::Int(_) | ExprVal::Float(_) | ExprVal::Bool(_) => {}
+        }
+    }
+
+    fn ident(&mut self, ident: &str) {
+        let (path, nested) = normalize_ident(ident);
+        for inner in nested {
+            self.ident(&inner);
+        }
+        if !path.is_empty() && !self.is_local(&path) {
+            self.spellings.push((path.clone(), ident.to_string()));
+            self.analysis.reads.insert(path);
+        }
+    }
+}
+
+/// Turn `a["b"].c[0]` into `a.b.c.0`. The path stops at the first subscript
+/// that is not a literal; identifiers used inside such subscripts are
+/// returned separately.
+fn normalize_ident(ident: &str) -> (String, Vec<String>) {
+    let mut path = String::new();
+    let mut nested = Vec::new();
+    let mut rest = ident;
+    let mut dynamic = false;
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').unwrap_or(after.len());
+            let inner = after[..end].trim();
+            rest = after.get(end + 1..).unwrap_or_default();
+            let literal = inner
+                .strip_prefix('"')
+                .and_then(|s| s.strip_suffix('"'))
+                .or_else(|| inner.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')))
+                .or_else(|| inner.parse::<usize>().is_ok().then_some(inner));
+            match literal {
+                Some(segment) if !dynamic => {
+                    path.push('.');
+                    path.push_str(segment);
+                }
+                Some(_) => {}
+                None => {
+                    nested.push(inner.to_string());
+                    dynamic = true;
+                }
+            }
+            continue;
+        }
+
+        let rest_trimmed = rest.strip_prefix('.').unwrap_or(rest);
+        let end = rest_trimmed.find(['.', '[']).unwrap_or(rest_trimmed.len());
+        if !dynamic {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(&rest_trimmed[..end]);
+        }
+        rest = &rest_trimmed[end..];
+    }
+
+    (path, nested)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_reads_and_skips_locals() {
+        let source = r#"
+{%- set bg = light.background -%}
+{{ bg }} {{ rgba(hex=dark["primary"], alpha=0.5) }}
+{% for name, hex in accents %}{{ name }}={{ hex | lowercase }}{% endfor %}
+{% if tokens.syntax.comment and probe is defined %}{{ ansi.dark.extended[3] }}{% endif %}
+{{ ui.sidebar[vars.side] }} {{ loop_free }}
+"#;
+        let reads = reads("test", source).unwrap();
+        assert_eq!(
+            reads.into_iter().collect::<Vec<_>>(),
+            vec![
+                "accents",
+                "ansi.dark.extended.3",
+                "dark.primary",
+                "light.background",
+                "loop_free",
+                "tokens.syntax.comment",
+                "ui.sidebar",
+                "vars.side",
+            ]
+        );
+    }
+
+    #[test]
+    fn locates_every_occurrence_of_a_read() {
+        let source = "{# dark.primary #}{{ dark.primary }}\n\
+                      dark.primary {{ dark[\"primary\"] }}\n\
+                      {{ dark.primary_alt }} {{ dark.primary }}\n\
+                      {{ with_alpha(color='red', alpha=0.5) }}\n";
+        let analysis = analyze("test", source).unwrap();
+        let lines: Vec<_> = analysis
+            .occurrences
+            .iter()
+            .map(|read| (read.path.as_str(), read.line))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("dark.primary", Some(1)),
+                ("dark.primary", Some(2)),
+                ("dark.primary_alt", Some(3)),
+                ("dark.primary", Some(3)),
+            ]
+        );
+        assert_eq!(analysis.color_args[0].line, Some(4));
+    }
+
+    #[test]
+    fn maps_context_paths_to_palette_keys() {
+        assert!(reads_key("light.primary", "colors.light.primary"));
+        assert!(reads_key("dark", "colors.dark.diff.added"));
+        assert!(reads_key("ansi.dark.extended.3", "ansi.dark.normal.red"));
+        assert!(reads_key("tokens.syntax", "tokens.syntax.comment.light"));
+        assert!(!reads_key("light.primary", "colors.light.primary_alt"));
+        assert!(!reads_key("vars.ui_font", "vars.ui_font"));
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+/// Non-color values from `[vars]`, with optional `[vars.light]` / `[vars.dark]` overrides.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Vars {
+    #[serde(flatten)]
+    pub values: BTreeMap<String, VarValue>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub light: BTreeMap<String, VarValue>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dark: BTreeMap<String, VarValue>,
+}
+
+impl Vars {
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty() && self.light.is_empty() && self.dark.is_empty()
+    }
+}
+
+/// A var value. Strings starting with `$` reference another var (`"$ui_font"`);
+/// use `$$` for a literal leading `$`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum VarValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<VarValue>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedVars {
+    pub light: BTreeMap<String, VarValue>,
+    pub dark: BTreeMap<String, VarValue>,
+    #[serde(flatten)]
+    pub values: BTreeMap<String, VarValue>,
+}
+
+/// Resolve var references for the base table and for each tone (base merged with overrides).
+pub fn resolve_vars(vars: &Vars) -> Result<ResolvedVars> {
+    let merged = |overrides: &BTreeMap<String, VarValue>| {
+        let mut all = vars.values.clone();
+        all.extend(overrides.iter().map(|(k, v)| (k.clone(), v.clone())));
+        all
+    };
+
+    Ok(ResolvedVars {
+        light: resolve_scope(&merged(&vars.light), "vars.light")?,
+        dark: resolve_scope(&merged(&vars.dark), "vars.dark")?,
+        values: resolve_scope(&vars.values, "vars")?,
+    })
+}
+
+fn resolve_scope(
+    scope: &BTreeMap<String, VarValue>,
+    base: &str,
+) -> Result<BTreeMap<String, VarValue>> {
+    let mut out = BTreeMap::new();
+    for (k, v) in scope {
+        let mut stack = vec![k.clone()];
+        let resolved = resolve_value(scope, v, &mut stack).map_err(|source| Error::ResolveVar {
+            label: format!("{base}.{k}"),
+            source: Box::new(source),
+        })?;
+        out.insert(k.clone(), resolved);
+    }
+    Ok(out)
+}
+
+fn resolve_value(
+    scope: &BTreeMap<String, VarValue>,
+    value: &VarValue,
+    stack: &mut Vec<String>,
+) -> Result<VarValue> {
+    match value {
+        VarValue::String(s) if s.starts_with("$$") => Ok(VarValue::String(s[1..].to_string())),
+        VarValue::String(s) if s.starts_with('$') => {
+            let name = &s[1..];
+            if stack.iter().any(|seen| seen == name) {
+                let cycle = stack.join(" -> ");
+                return Err(Error::Cycle(format!("{cycle} -> {name}")));
+            }
+            let Some(target) = scope.get(name) else {
+                return Err(Error::MissingVar(name.to_string()));
+            };
+            stack.push(name.to_string());
+            let resolved = resolve_value(scope, target, stack)?;
+            stack.pop();
+            Ok(resolved)
+        }
+        VarValue::Array(items) => Ok(VarValue::Array(
+            items
+                .iter()
+                .map(|item| resolve_value(scope, item, stack))
+                .collect::<Result<_>>()?,
+        )),
+        other => Ok(other.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARS_TOML: &str = r#"
+ui_font = "Inter"
+code_font = "$ui_font"
+font_size = 13
+line_height = 1.5
+italic_comments = true
+features = ["calt", "$ui_font"]
+price = "$$5"
+
+[light]
+italic_comments = false
+
+[dark]
+ui_font = "Inter Display"
+"#;
+
+    #[test]
+    fn resolves_references_and_overrides() {
+        let vars: Vars = toml::from_str(VARS_TOML).unwrap();
+        let resolved = resolve_vars(&vars).unwrap();
+
+        assert_eq!(
+            resolved.values["code_font"],
+            VarValue::String("Inter".into())
+        );
+        assert_eq!(resolved.values["font_size"], VarValue::Integer(13));
+        assert_eq!(resolved.values["line_height"], VarValue::Float(1.5));
+        assert_eq!(resolved.values["price"], VarValue::String("$5".into()));
+        assert_eq!(resolved.light["italic_comments"], VarValue::Bool(false));
+        assert_eq!(
+            resolved.dark["code_font"],
+            VarValue::String("Inter Display".into()),
+            "references see the tone's overrides"
+        );
+        assert_eq!(
+            resolved.dark["features"],
+            VarValue::Array(vec![
+                VarValue::String("calt".into()),
+                VarValue::String("Inter Display".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn detects_var_cycles_and_missing_refs() {
+        let vars: Vars = toml::from_str("a = \"$b\"\nb = \"$a\"").unwrap();
+        let err = anyhow::Error::from(resolve_vars(&vars).unwrap_err());
+        assert!(
+            format!("{err:#}").contains("cycle detected"),
+            "unexpected error: {err:#}"
+        );
+
+        let vars: Vars = toml::from_str("a = \"$nope\"").unwrap();
+        let err = anyhow::Error::from(resolve_vars(&vars).unwrap_err());
+        assert!(
+            format!("{err:#}").contains("missing var 'nope'"),
+            "unexpected error: {err:#}"
+        );
+    }
+}